@@ -26,6 +26,22 @@ pub enum Commands {
         #[command(subcommand)]
         subcommand: TxCommands,
     },
+    Contract {
+        #[command(subcommand)]
+        subcommand: ContractCommands,
+    },
+    Token {
+        #[command(subcommand)]
+        subcommand: TokenCommands,
+    },
+    Nft {
+        #[command(subcommand)]
+        subcommand: NftCommands,
+    },
+    Sign {
+        #[command(subcommand)]
+        subcommand: SignCommands,
+    },
 }
 
 #[derive(Subcommand)]
@@ -40,8 +56,9 @@ pub enum AccountCommands {
     Logout,
     Balance,
     BalanceToken {
-        token_address: String,
+        token: String,
     },
+    Portfolio,
     Info,
 }
 
@@ -63,7 +80,12 @@ pub enum NetworkCommands {
         network_name: String,
         url: String,
     },
+    SetEns {
+        network_name: String,
+        registry: Option<String>,
+    },
     Info,
+    Gas,
 }
 
 #[derive(Subcommand)]
@@ -73,8 +95,12 @@ pub enum TxCommands {
         destination_address: String,
         #[arg(long)]
         gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
         #[arg(long)]
         gas_limit: Option<String>,
+        #[arg(long)]
+        yes: bool,
     },
     SendToken {
         amount: String,
@@ -82,11 +108,277 @@ pub enum TxCommands {
         token_address: String,
         #[arg(long)]
         gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
         #[arg(long)]
         gas_limit: Option<String>,
+        #[arg(long)]
+        yes: bool,
+    },
+    History {
+        #[arg(long, value_parser = ["csv", "json"])]
+        export: Option<String>,
+        #[arg(long, requires = "export")]
+        out: Option<String>,
+        #[arg(long)]
+        all_networks: bool,
+        #[arg(long, value_parser = ["in", "out", "self"])]
+        direction: Option<String>,
+        #[arg(long)]
+        token: Option<String>,
+        #[arg(long)]
+        counterparty: Option<String>,
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        until: Option<String>,
+        #[arg(long, value_parser = ["pending", "success", "failed", "replaced", "dropped", "unknown"])]
+        status: Option<String>,
+        #[arg(long)]
+        min_amount: Option<String>,
+        #[arg(long, value_parser = ["date", "amount"])]
+        sort: Option<String>,
+        #[arg(long)]
+        desc: bool,
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+        #[arg(long)]
+        limit: Option<usize>,
+        #[arg(long)]
+        compact: bool,
+    },
+    Sync {
+        #[arg(long)]
+        from_block: Option<u64>,
+        #[arg(long)]
+        chunk_size: Option<u64>,
     },
-    History,
     Info {
         transaction_hash: String,
+        #[arg(long)]
+        abi: Vec<String>,
+    },
+    SpeedUp {
+        transaction_hash: String,
+        #[arg(long)]
+        gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
+        #[arg(long)]
+        yes: bool,
+    },
+    Cancel {
+        transaction_hash: String,
+        #[arg(long)]
+        gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
+        #[arg(long)]
+        yes: bool,
+    },
+    Sign {
+        #[arg(long)]
+        to: String,
+        #[arg(long, default_value = "0")]
+        value: String,
+        #[arg(long)]
+        nonce: u64,
+        #[arg(long)]
+        chain_id: u64,
+        #[arg(long)]
+        gas_limit: String,
+        #[arg(long)]
+        gas_price: Option<String>,
+        #[arg(long)]
+        max_fee: Option<String>,
+        #[arg(long)]
+        max_priority_fee: Option<String>,
+        #[arg(long)]
+        data: Option<String>,
+    },
+    Broadcast {
+        raw_transaction: String,
+    },
+    Prepare {
+        amount: String,
+        destination_address: String,
+        #[arg(long)]
+        token: Option<String>,
+        #[arg(long)]
+        gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
+        #[arg(long)]
+        gas_limit: Option<String>,
+        #[arg(long)]
+        out: String,
+    },
+    SignFile {
+        file: String,
+        #[arg(long)]
+        out: String,
+        #[arg(long)]
+        yes: bool,
+    },
+    BroadcastFile {
+        file: String,
+    },
+    Call {
+        contract_address: String,
+        signature: String,
+        #[arg(allow_hyphen_values = true)]
+        args: Vec<String>,
+        #[arg(long)]
+        abi: Option<String>,
+        #[arg(long)]
+        value: Option<String>,
+        #[arg(long)]
+        gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
+        #[arg(long)]
+        gas_limit: Option<String>,
+        #[arg(long)]
+        yes: bool,
+    },
+    Deploy {
+        bytecode_file: String,
+        #[arg(allow_hyphen_values = true)]
+        args: Vec<String>,
+        #[arg(long)]
+        abi: Option<String>,
+        #[arg(long)]
+        value: Option<String>,
+        #[arg(long)]
+        gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
+        #[arg(long)]
+        gas_limit: Option<String>,
+        #[arg(long)]
+        yes: bool,
+    },
+    Batch {
+        file: String,
+        #[arg(long)]
+        gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ContractCommands {
+    Read {
+        contract_address: String,
+        signature: String,
+        #[arg(allow_hyphen_values = true)]
+        args: Vec<String>,
+        #[arg(long)]
+        abi: Option<String>,
+        #[arg(long)]
+        block: Option<String>,
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TokenCommands {
+    Approve {
+        token_address: String,
+        spender: String,
+        amount: String,
+        #[arg(long)]
+        gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
+        #[arg(long)]
+        gas_limit: Option<String>,
+        #[arg(long)]
+        yes: bool,
+    },
+    Allowance {
+        token_address: String,
+        spender: String,
+    },
+    Revoke {
+        token_address: String,
+        spender: String,
+        #[arg(long)]
+        gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
+        #[arg(long)]
+        gas_limit: Option<String>,
+        #[arg(long)]
+        yes: bool,
+    },
+    Add {
+        token_address: String,
+    },
+    Remove {
+        token: String,
+    },
+    List,
+    Import {
+        file: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NftCommands {
+    Owner {
+        collection_address: String,
+        token_id: String,
+    },
+    Balance {
+        collection_address: String,
+        #[arg(long)]
+        id: Option<String>,
+    },
+    Uri {
+        collection_address: String,
+        token_id: String,
+    },
+    Transfer {
+        collection_address: String,
+        destination_address: String,
+        token_id: String,
+        #[arg(long)]
+        amount: Option<String>,
+        #[arg(long)]
+        gas_price: Option<String>,
+        #[arg(long, value_parser = ["slow", "normal", "fast"], conflicts_with = "gas_price")]
+        speed: Option<String>,
+        #[arg(long)]
+        gas_limit: Option<String>,
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SignCommands {
+    Message {
+        message: Option<String>,
+        #[arg(long)]
+        file: Option<String>,
+        #[arg(long)]
+        hex: bool,
+    },
+    Verify {
+        message: String,
+        signature: String,
+        address: Option<String>,
+        #[arg(long)]
+        hex: bool,
+    },
+    TypedData {
+        file: String,
+        #[arg(long)]
+        yes: bool,
     },
 }
@@ -2,6 +2,21 @@ pub const STORAGE_DIR: &str = "storage";
 pub const STATE_FILE: &str = "storage/state.json";
 pub const STORAGE_FILE: &str = "storage/networks.json";
 
+pub const SYNC_CHUNK_SIZE: u64 = 2_000;
+pub const SYNC_DEFAULT_LOOKBACK: u64 = 50_000;
+
+pub const POLICY_WINDOW_SECONDS: u64 = 86_400;
+
+pub const FEE_HISTORY_BLOCKS: u64 = 20;
+pub const FEE_REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+pub const NATIVE_TRANSFER_GAS: u64 = 21_000;
+pub const ERC20_TRANSFER_GAS: u64 = 65_000;
+
+pub const ENS_REGISTRY: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+
+pub const OP_STACK_CHAIN_IDS: [u64; 4] = [10, 11155420, 8453, 84532];
+pub const OP_GAS_PRICE_ORACLE: &str = "0x420000000000000000000000000000000000000F";
+
 pub const ERC20_ABI: &str = r#"
 [
     {
@@ -21,5 +36,183 @@ pub const ERC20_ABI: &str = r#"
         "payable": false,
         "stateMutability": "nonpayable",
         "type": "function"
+    },
+    {
+        "constant": false,
+        "inputs": [{"name": "_spender", "type": "address"}, {"name": "_value", "type": "uint256"}],
+        "name": "approve",
+        "outputs": [{"name": "", "type": "bool"}],
+        "payable": false,
+        "stateMutability": "nonpayable",
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [{"name": "_owner", "type": "address"}, {"name": "_spender", "type": "address"}],
+        "name": "allowance",
+        "outputs": [{"name": "", "type": "uint256"}],
+        "payable": false,
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [],
+        "name": "decimals",
+        "outputs": [{"name": "", "type": "uint8"}],
+        "payable": false,
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [],
+        "name": "symbol",
+        "outputs": [{"name": "", "type": "string"}],
+        "payable": false,
+        "stateMutability": "view",
+        "type": "function"
+    }
+]"#;
+
+pub const ERC165_ABI: &str = r#"
+[
+    {
+        "inputs": [{"name": "interfaceId", "type": "bytes4"}],
+        "name": "supportsInterface",
+        "outputs": [{"name": "", "type": "bool"}],
+        "stateMutability": "view",
+        "type": "function"
     }
 ]"#;
+
+pub const ERC721_ABI: &str = r#"
+[
+    {
+        "inputs": [{"name": "owner", "type": "address"}],
+        "name": "balanceOf",
+        "outputs": [{"name": "", "type": "uint256"}],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "inputs": [{"name": "tokenId", "type": "uint256"}],
+        "name": "ownerOf",
+        "outputs": [{"name": "", "type": "address"}],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "inputs": [{"name": "tokenId", "type": "uint256"}],
+        "name": "tokenURI",
+        "outputs": [{"name": "", "type": "string"}],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "inputs": [{"name": "from", "type": "address"}, {"name": "to", "type": "address"}, {"name": "tokenId", "type": "uint256"}],
+        "name": "safeTransferFrom",
+        "outputs": [],
+        "stateMutability": "nonpayable",
+        "type": "function"
+    }
+]"#;
+
+pub const ERC1155_ABI: &str = r#"
+[
+    {
+        "inputs": [{"name": "account", "type": "address"}, {"name": "id", "type": "uint256"}],
+        "name": "balanceOf",
+        "outputs": [{"name": "", "type": "uint256"}],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "inputs": [{"name": "id", "type": "uint256"}],
+        "name": "uri",
+        "outputs": [{"name": "", "type": "string"}],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "inputs": [
+            {"name": "from", "type": "address"},
+            {"name": "to", "type": "address"},
+            {"name": "id", "type": "uint256"},
+            {"name": "amount", "type": "uint256"},
+            {"name": "data", "type": "bytes"}
+        ],
+        "name": "safeTransferFrom",
+        "outputs": [],
+        "stateMutability": "nonpayable",
+        "type": "function"
+    }
+]"#;
+
+// Human-readable ABI decoded by `tx info` without a user ABI. On selector clashes the first entry wins.
+pub const KNOWN_FUNCTION_SIGNATURES: &[&str] = &[
+    // ERC-20
+    "transfer(address to, uint256 amount)",
+    "approve(address spender, uint256 amount)",
+    "transferFrom(address from, address to, uint256 amount)",
+    "increaseAllowance(address spender, uint256 addedValue)",
+    "decreaseAllowance(address spender, uint256 subtractedValue)",
+    "permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s)",
+    // ERC-721
+    "safeTransferFrom(address from, address to, uint256 tokenId)",
+    "safeTransferFrom(address from, address to, uint256 tokenId, bytes data)",
+    "setApprovalForAll(address operator, bool approved)",
+    // ERC-1155
+    "safeTransferFrom(address from, address to, uint256 id, uint256 amount, bytes data)",
+    "safeBatchTransferFrom(address from, address to, uint256[] ids, uint256[] amounts, bytes data)",
+    // WETH
+    "deposit()",
+    "withdraw(uint256 amount)",
+    // Uniswap V2 router
+    "swapExactTokensForTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline)",
+    "swapTokensForExactTokens(uint256 amountOut, uint256 amountInMax, address[] path, address to, uint256 deadline)",
+    "swapExactETHForTokens(uint256 amountOutMin, address[] path, address to, uint256 deadline)",
+    "swapETHForExactTokens(uint256 amountOut, address[] path, address to, uint256 deadline)",
+    "swapExactTokensForETH(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline)",
+    "swapTokensForExactETH(uint256 amountOut, uint256 amountInMax, address[] path, address to, uint256 deadline)",
+    "addLiquidity(address tokenA, address tokenB, uint256 amountADesired, uint256 amountBDesired, uint256 amountAMin, uint256 amountBMin, address to, uint256 deadline)",
+    "addLiquidityETH(address token, uint256 amountTokenDesired, uint256 amountTokenMin, uint256 amountETHMin, address to, uint256 deadline)",
+    "removeLiquidity(address tokenA, address tokenB, uint256 liquidity, uint256 amountAMin, uint256 amountBMin, address to, uint256 deadline)",
+    "removeLiquidityETH(address token, uint256 liquidity, uint256 amountTokenMin, uint256 amountETHMin, address to, uint256 deadline)",
+    // Uniswap V3 router
+    "struct ExactInputSingleParams { address tokenIn; address tokenOut; uint24 fee; address recipient; uint256 deadline; uint256 amountIn; uint256 amountOutMinimum; uint160 sqrtPriceLimitX96; }",
+    "struct ExactOutputSingleParams { address tokenIn; address tokenOut; uint24 fee; address recipient; uint256 deadline; uint256 amountOut; uint256 amountInMaximum; uint160 sqrtPriceLimitX96; }",
+    "struct ExactInputParams { bytes path; address recipient; uint256 deadline; uint256 amountIn; uint256 amountOutMinimum; }",
+    "struct ExactOutputParams { bytes path; address recipient; uint256 deadline; uint256 amountOut; uint256 amountInMaximum; }",
+    "exactInputSingle(ExactInputSingleParams params)",
+    "exactOutputSingle(ExactOutputSingleParams params)",
+    "exactInput(ExactInputParams params)",
+    "exactOutput(ExactOutputParams params)",
+    "multicall(bytes[] data)",
+    "multicall(uint256 deadline, bytes[] data)",
+    // Uniswap Universal Router
+    "execute(bytes commands, bytes[] inputs, uint256 deadline)",
+    "execute(bytes commands, bytes[] inputs)",
+];
+
+// Events decoded in `tx info` receipts. Candidates sharing a topic are told apart by their indexed parameters.
+pub const KNOWN_EVENT_SIGNATURES: &[&str] = &[
+    // ERC-20
+    "event Transfer(address indexed from, address indexed to, uint256 value)",
+    "event Approval(address indexed owner, address indexed spender, uint256 value)",
+    // ERC-721
+    "event Transfer(address indexed from, address indexed to, uint256 indexed tokenId)",
+    "event Approval(address indexed owner, address indexed approved, uint256 indexed tokenId)",
+    "event ApprovalForAll(address indexed owner, address indexed operator, bool approved)",
+    // ERC-1155
+    "event TransferSingle(address indexed operator, address indexed from, address indexed to, uint256 id, uint256 value)",
+    "event TransferBatch(address indexed operator, address indexed from, address indexed to, uint256[] ids, uint256[] values)",
+    // WETH
+    "event Deposit(address indexed dst, uint256 wad)",
+    "event Withdrawal(address indexed src, uint256 wad)",
+    // Uniswap V2 pair
+    "event Swap(address indexed sender, uint256 amount0In, uint256 amount1In, uint256 amount0Out, uint256 amount1Out, address indexed to)",
+    "event Sync(uint112 reserve0, uint112 reserve1)",
+    // Uniswap V3 pool
+    "event Swap(address indexed sender, address indexed recipient, int256 amount0, int256 amount1, uint160 sqrtPriceX96, uint128 liquidity, int24 tick)",
+];
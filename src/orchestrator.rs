@@ -1,10 +1,19 @@
-use crate::commands::{Commands, AccountCommands, NetworkCommands, TxCommands};
+use std::error::Error;
+use crate::commands::{
+    Commands, AccountCommands, ContractCommands, NetworkCommands, NftCommands, SignCommands, TokenCommands, TxCommands,
+};
 use super::services::{account::AccountService, network::NetworkService, transaction::TransactionService};
+use super::services::offline::{OfflineService, OfflineTxParams, SignedTxFile, UnsignedTxFile};
+use super::services::fees::FeeSpeed;
+use super::services::history::HistoryQuery;
+use super::services::network::NetworkInfo;
+use super::services::signing::SigningService;
 
 pub struct Orchestrator {
     account_service: AccountService,
     network_service: NetworkService,
     transaction_service: TransactionService,
+    signing_service: SigningService,
 }
 
 impl Orchestrator {
@@ -13,11 +22,13 @@ impl Orchestrator {
             account_service: AccountService,
             network_service: NetworkService::new(),
             transaction_service: TransactionService::new(),
+            signing_service: SigningService::new(),
         }
     }
 
     pub async fn handle_command(&mut self, command: &Commands) {
         if let Some(wallet) = AccountService::get_wallet() {
+            self.signing_service.set_wallet(wallet.clone());
             self.transaction_service.set_wallet(wallet);
         } else {
             eprintln!("Warning: Wallet not set. Please log in or create a wallet");
@@ -29,16 +40,33 @@ impl Orchestrator {
             eprintln!("Warning: Provider URL not set. Some commands may not work until the provider is set.");
         }
 
+        if let Some(network) = self.network_service.get_current_network() {
+            self.signing_service.set_network(network.clone());
+            self.transaction_service.set_network(network.clone());
+        }
+
         match command {
             Commands::Account { subcommand } => {
                 self.handle_account_commands(subcommand).await;
             }
             Commands::Network { subcommand } => {
-                self.handle_network_commands(subcommand);
+                self.handle_network_commands(subcommand).await;
             }
             Commands::Tx { subcommand } => {
                 self.handle_tx_commands(subcommand).await;
             }
+            Commands::Contract { subcommand } => {
+                self.handle_contract_commands(subcommand).await;
+            }
+            Commands::Token { subcommand } => {
+                self.handle_token_commands(subcommand).await;
+            }
+            Commands::Nft { subcommand } => {
+                self.handle_nft_commands(subcommand).await;
+            }
+            Commands::Sign { subcommand } => {
+                self.handle_sign_commands(subcommand).await;
+            }
         }
     }
 
@@ -62,18 +90,25 @@ impl Orchestrator {
                     eprintln!("Failed to retrieve balance: {}", e);
                 }
             }
-            AccountCommands::BalanceToken { token_address } => {
-                if let Err(e) = self.transaction_service.get_token_balance(token_address).await {
+            AccountCommands::BalanceToken { token } => {
+                let network_name = self.network_service.get_network_name();
+                if let Err(e) = self.transaction_service.get_token_balance(token, network_name.unwrap().as_str()).await {
                     eprintln!("Failed to retrieve token balance: {}", e);
                 }
             }
+            AccountCommands::Portfolio => {
+                let network_name = self.network_service.get_network_name();
+                if let Err(e) = self.transaction_service.portfolio(network_name.unwrap().as_str()).await {
+                    eprintln!("Failed to retrieve portfolio: {}", e);
+                }
+            }
             AccountCommands::Info => {
                 AccountService::account_info();
             }
         }
     }
 
-    pub fn handle_network_commands(&mut self, command: &NetworkCommands) {
+    pub async fn handle_network_commands(&mut self, command: &NetworkCommands) {
         match command {
             NetworkCommands::Switch { network_name, url } => {
                 self.network_service.switch_network(network_name, url.as_deref());
@@ -87,9 +122,18 @@ impl Orchestrator {
             NetworkCommands::SetUrl { network_name, url } => {
                 self.network_service.set_network_url(network_name, url);
             }
+            NetworkCommands::SetEns { network_name, registry } => {
+                self.network_service.set_ens_registry(network_name, registry.as_deref());
+            }
             NetworkCommands::Info => {
                 self.network_service.network_info();
             }
+            NetworkCommands::Gas => {
+                let network_name = self.network_service.get_network_name();
+                if let Err(e) = self.transaction_service.gas_options(network_name.unwrap().as_str()).await {
+                    println!("Failed to estimate gas fees: {}", e);
+                }
+            }
         }
     }
 
@@ -99,11 +143,14 @@ impl Orchestrator {
                 amount,
                 destination_address,
                 gas_price,
+                speed,
                 gas_limit,
+                yes,
             } => {
                 let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
                 if let Err(e) = self.transaction_service.send(
-                    destination_address, amount, gas_price.as_deref(), gas_limit.as_deref(), network_name.unwrap().as_str()
+                    destination_address, amount, gas_price.as_deref(), speed, gas_limit.as_deref(), network_name.unwrap().as_str(), *yes
                 ).await {
                     println!("Failed to send transaction: {}", e);
                 }
@@ -113,24 +160,372 @@ impl Orchestrator {
                 destination_address,
                 token_address,
                 gas_price,
+                speed,
                 gas_limit,
+                yes,
             } => {
                 let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
                 if let Err(e) = self.transaction_service.send_token(
-                    destination_address, amount, token_address, gas_price.as_deref(), gas_limit.as_deref(), network_name.unwrap().as_str()
+                    destination_address, amount, token_address, gas_price.as_deref(), speed, gas_limit.as_deref(), network_name.unwrap().as_str(), *yes
                 ).await {
                     println!("Failed to send transaction: {}", e);
                 }
             }
-            TxCommands::History => {
+            TxCommands::History {
+                export,
+                out,
+                all_networks,
+                direction,
+                token,
+                counterparty,
+                since,
+                until,
+                status,
+                min_amount,
+                sort,
+                desc,
+                offset,
+                limit,
+                compact,
+            } => {
+                let network_name = self.network_service.get_network_name();
+                let query = HistoryQuery {
+                    direction: direction.clone(),
+                    token: token.clone(),
+                    counterparty: counterparty.clone(),
+                    since: since.clone(),
+                    until: until.clone(),
+                    status: status.clone(),
+                    min_amount: min_amount.clone(),
+                    sort: sort.clone(),
+                    desc: *desc,
+                    offset: *offset,
+                    limit: *limit,
+                };
+                let result = match export {
+                    Some(format) => {
+                        let mut networks: Vec<(String, NetworkInfo)> = self
+                            .network_service
+                            .networks
+                            .iter()
+                            .filter(|(key, _)| *all_networks || Some(*key) == network_name.as_ref())
+                            .map(|(key, network)| (key.clone(), network.clone()))
+                            .collect();
+                        networks.sort_by(|a, b| a.0.cmp(&b.0));
+                        self.transaction_service
+                            .export_history(&networks, network_name.as_deref().unwrap_or_default(), query, format, out.as_deref())
+                            .await
+                    }
+                    None => self.transaction_service.history(network_name.unwrap().as_str(), query, *compact).await,
+                };
+                if let Err(e) = result {
+                    println!("Failed to show transaction history: {}", e);
+                }
+            }
+            TxCommands::Sync { from_block, chunk_size } => {
                 let network_name = self.network_service.get_network_name();
-                self.transaction_service.history(network_name.unwrap().as_str());
+                if let Err(e) = self.transaction_service.sync(network_name.unwrap().as_str(), *from_block, *chunk_size).await {
+                    println!("Failed to sync transaction history: {}", e);
+                }
             }
-            TxCommands::Info { transaction_hash } => {
-                if let Err(e) = self.transaction_service.info(transaction_hash).await {
+            TxCommands::Info { transaction_hash, abi } => {
+                if let Err(e) = self.transaction_service.info(transaction_hash, abi).await {
                     println!("Failed to retrieve transaction info: {}", e);
                 }
             }
+            TxCommands::SpeedUp { transaction_hash, gas_price, speed, yes } => {
+                let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
+                if let Err(e) = self.transaction_service.replace(
+                    transaction_hash, false, gas_price.as_deref(), speed, network_name.unwrap().as_str(), *yes
+                ).await {
+                    println!("Failed to speed up transaction: {}", e);
+                }
+            }
+            TxCommands::Cancel { transaction_hash, gas_price, speed, yes } => {
+                let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
+                if let Err(e) = self.transaction_service.replace(
+                    transaction_hash, true, gas_price.as_deref(), speed, network_name.unwrap().as_str(), *yes
+                ).await {
+                    println!("Failed to cancel transaction: {}", e);
+                }
+            }
+            TxCommands::Sign {
+                to,
+                value,
+                nonce,
+                chain_id,
+                gas_limit,
+                gas_price,
+                max_fee,
+                max_priority_fee,
+                data,
+            } => {
+                let params = OfflineTxParams {
+                    to,
+                    value,
+                    nonce: *nonce,
+                    chain_id: *chain_id,
+                    gas_limit,
+                    gas_price: gas_price.as_deref(),
+                    max_fee: max_fee.as_deref(),
+                    max_priority_fee: max_priority_fee.as_deref(),
+                    data: data.as_deref(),
+                };
+                let network_name = self
+                    .network_service
+                    .get_network_name()
+                    .filter(|_| self.network_service.get_current_network().is_some_and(|network| network.chain_id == *chain_id));
+                if let Err(e) = self.transaction_service.sign_offline(&params, network_name.as_deref()) {
+                    println!("Failed to sign transaction: {}", e);
+                }
+            }
+            TxCommands::Broadcast { raw_transaction } => {
+                let network_name = self.network_service.get_network_name();
+                if let Err(e) = self.transaction_service.broadcast(raw_transaction, network_name.unwrap().as_str()).await {
+                    println!("Failed to broadcast transaction: {}", e);
+                }
+            }
+            TxCommands::Prepare {
+                amount,
+                destination_address,
+                token,
+                gas_price,
+                speed,
+                gas_limit,
+                out,
+            } => {
+                let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
+                if let Err(e) = self.transaction_service.prepare(
+                    destination_address, amount, token.as_deref(), gas_price.as_deref(), speed, gas_limit.as_deref(), network_name.unwrap().as_str(), out
+                ).await {
+                    println!("Failed to prepare transaction: {}", e);
+                }
+            }
+            TxCommands::SignFile { file, out, yes } => {
+                if let Err(e) = self.sign_file(file, out, *yes) {
+                    println!("Failed to sign transaction: {}", e);
+                }
+            }
+            TxCommands::BroadcastFile { file } => {
+                if let Err(e) = self.broadcast_file(file).await {
+                    println!("Failed to broadcast transaction: {}", e);
+                }
+            }
+            TxCommands::Call {
+                contract_address,
+                signature,
+                args,
+                abi,
+                value,
+                gas_price,
+                speed,
+                gas_limit,
+                yes,
+            } => {
+                let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
+                if let Err(e) = self.transaction_service.call_contract(
+                    contract_address, signature, args, abi.as_deref(), value.as_deref(), gas_price.as_deref(), speed, gas_limit.as_deref(), network_name.unwrap().as_str(), *yes
+                ).await {
+                    println!("Failed to send contract call: {}", e);
+                }
+            }
+            TxCommands::Deploy {
+                bytecode_file,
+                args,
+                abi,
+                value,
+                gas_price,
+                speed,
+                gas_limit,
+                yes,
+            } => {
+                let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
+                if let Err(e) = self.transaction_service.deploy(
+                    bytecode_file, args, abi.as_deref(), value.as_deref(), gas_price.as_deref(), speed, gas_limit.as_deref(), network_name.unwrap().as_str(), *yes
+                ).await {
+                    println!("Failed to deploy contract: {}", e);
+                }
+            }
+            TxCommands::Batch { file, gas_price, speed, yes } => {
+                let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
+                if let Err(e) = self.transaction_service.batch(
+                    file, gas_price.as_deref(), speed, network_name.unwrap().as_str(), *yes
+                ).await {
+                    println!("Failed to send batch: {}", e);
+                }
+            }
+        }
+    }
+
+    pub async fn handle_contract_commands(&mut self, command: &ContractCommands) {
+        match command {
+            ContractCommands::Read {
+                contract_address,
+                signature,
+                args,
+                abi,
+                block,
+                json,
+            } => {
+                if let Err(e) = self.transaction_service.read_contract(
+                    contract_address, signature, args, abi.as_deref(), block.as_deref(), *json
+                ).await {
+                    println!("Failed to read contract: {}", e);
+                }
+            }
         }
     }
+
+    pub async fn handle_token_commands(&mut self, command: &TokenCommands) {
+        match command {
+            TokenCommands::Approve {
+                token_address,
+                spender,
+                amount,
+                gas_price,
+                speed,
+                gas_limit,
+                yes,
+            } => {
+                let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
+                if let Err(e) = self.transaction_service.approve(
+                    token_address, spender, amount, gas_price.as_deref(), speed, gas_limit.as_deref(), network_name.unwrap().as_str(), *yes
+                ).await {
+                    println!("Failed to approve: {}", e);
+                }
+            }
+            TokenCommands::Allowance { token_address, spender } => {
+                if let Err(e) = self.transaction_service.get_allowance(token_address, spender).await {
+                    eprintln!("Failed to retrieve allowance: {}", e);
+                }
+            }
+            TokenCommands::Revoke {
+                token_address,
+                spender,
+                gas_price,
+                speed,
+                gas_limit,
+                yes,
+            } => {
+                let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
+                if let Err(e) = self.transaction_service.approve(
+                    token_address, spender, "0", gas_price.as_deref(), speed, gas_limit.as_deref(), network_name.unwrap().as_str(), *yes
+                ).await {
+                    println!("Failed to revoke approval: {}", e);
+                }
+            }
+            TokenCommands::Add { token_address } => {
+                let network_name = self.network_service.get_network_name();
+                if let Err(e) = self.transaction_service.add_token(token_address, network_name.unwrap().as_str()).await {
+                    println!("Failed to add token: {}", e);
+                }
+            }
+            TokenCommands::Remove { token } => {
+                let network_name = self.network_service.get_network_name();
+                if let Err(e) = self.transaction_service.remove_token(token, network_name.unwrap().as_str()) {
+                    println!("Failed to remove token: {}", e);
+                }
+            }
+            TokenCommands::List => {
+                let network_name = self.network_service.get_network_name();
+                if let Err(e) = self.transaction_service.list_tokens(network_name.unwrap().as_str()) {
+                    println!("Failed to list tokens: {}", e);
+                }
+            }
+            TokenCommands::Import { file } => {
+                let network_name = self.network_service.get_network_name();
+                if let Err(e) = self.transaction_service.import_token_list(file, network_name.unwrap().as_str()) {
+                    println!("Failed to import token list: {}", e);
+                }
+            }
+        }
+    }
+
+    pub async fn handle_nft_commands(&mut self, command: &NftCommands) {
+        match command {
+            NftCommands::Owner { collection_address, token_id } => {
+                if let Err(e) = self.transaction_service.nft_owner(collection_address, token_id).await {
+                    eprintln!("Failed to retrieve owner: {}", e);
+                }
+            }
+            NftCommands::Balance { collection_address, id } => {
+                if let Err(e) = self.transaction_service.nft_balance(collection_address, id.as_deref()).await {
+                    eprintln!("Failed to retrieve NFT balance: {}", e);
+                }
+            }
+            NftCommands::Uri { collection_address, token_id } => {
+                if let Err(e) = self.transaction_service.nft_uri(collection_address, token_id).await {
+                    eprintln!("Failed to retrieve metadata URI: {}", e);
+                }
+            }
+            NftCommands::Transfer {
+                collection_address,
+                destination_address,
+                token_id,
+                amount,
+                gas_price,
+                speed,
+                gas_limit,
+                yes,
+            } => {
+                let network_name = self.network_service.get_network_name();
+                let speed = speed.as_deref().and_then(|speed| speed.parse::<FeeSpeed>().ok());
+                if let Err(e) = self.transaction_service.nft_transfer(
+                    collection_address, destination_address, token_id, amount.as_deref(), gas_price.as_deref(), speed, gas_limit.as_deref(), network_name.unwrap().as_str(), *yes
+                ).await {
+                    println!("Failed to transfer NFT: {}", e);
+                }
+            }
+        }
+    }
+
+    pub async fn handle_sign_commands(&mut self, command: &SignCommands) {
+        match command {
+            SignCommands::Message { message, file, hex } => {
+                let result = match SigningService::message_bytes(message.as_deref(), file.as_deref(), *hex) {
+                    Ok(bytes) => self.signing_service.sign_message(&bytes).await.map(|_| ()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    println!("Failed to sign message: {}", e);
+                }
+            }
+            SignCommands::Verify { message, signature, address, hex } => {
+                let result = SigningService::message_bytes(Some(message), None, *hex)
+                    .and_then(|bytes| SigningService::verify_message(&bytes, signature, address.as_deref()));
+                if let Err(e) = result {
+                    println!("Failed to verify signature: {}", e);
+                }
+            }
+            SignCommands::TypedData { file, yes } => {
+                if let Err(e) = self.signing_service.sign_typed_data(file, *yes).await {
+                    println!("Failed to sign typed data: {}", e);
+                }
+            }
+        }
+    }
+
+    fn sign_file(&mut self, file: &str, out: &str, skip_confirmation: bool) -> Result<String, Box<dyn Error>> {
+        let unsigned: UnsignedTxFile = OfflineService::read_file(file)?;
+        OfflineService::check_version(unsigned.version)?;
+        self.network_service.ensure_network_chain_id(&unsigned.network.name, unsigned.network.chain_id)?;
+        self.transaction_service.sign_file(&unsigned, out, skip_confirmation)
+    }
+
+    async fn broadcast_file(&mut self, file: &str) -> Result<String, Box<dyn Error>> {
+        let signed: SignedTxFile = OfflineService::read_file(file)?;
+        OfflineService::check_version(signed.version)?;
+        self.network_service.ensure_current_chain_id(signed.network.chain_id)?;
+        let network_name = self.network_service.get_network_name().ok_or("No network is currently selected.")?;
+        self.transaction_service.broadcast_file(&signed, &network_name).await
+    }
 }
@@ -16,6 +16,7 @@ use aes_gcm::{
     aead::{Aead, KeyInit, Nonce},
     Aes256Gcm, Key
 };
+use crate::services::address::checksum;
 use crate::services::crypto::CryptoService;
 use crate::config::{STORAGE_DIR, STATE_FILE};
 
@@ -78,46 +79,18 @@ impl AccountService {
     }
 
     pub fn login(account_name: &str) {
-        let account_file = Path::new(STORAGE_DIR).join(account_name).join("account_info.json");
-        let account_data = match fs::read_to_string(&account_file) {
-            Ok(data) => data,
-            Err(e) => {
-                println!("Failed to read account data: {}", e);
-                return;
-            }
-        };
+        let password = AccountService::get_password("Enter your password: ");
 
-        let account_json: Value = match serde_json::from_str(&account_data) {
-            Ok(json) => json,
-            Err(e) => {
-                println!("Failed to parse account data: {}", e);
+        match AccountService::verify_password(account_name, &password) {
+            Ok(true) => {}
+            Ok(false) => {
+                println!("Incorrect password. Please try again.");
                 return;
             }
-        };
-
-        let password = AccountService::get_password("Enter your password: ");
-
-        let encryption_key_str = account_json["encryption_key"].as_str().unwrap();
-        let encryption_key_bytes = hex::decode(encryption_key_str).expect("Failed to decode encryption key");
-        let encryption_key = Key::<Aes256Gcm>::from_slice(&encryption_key_bytes);
-
-        let encrypted_password = account_json["encrypted_password"].as_str().unwrap();
-
-        let password_nonce_str = account_json["password_nonce"].as_str().unwrap();
-        let password_nonce_bytes = hex::decode(password_nonce_str).expect("Failed to decode password nonce");
-        let password_nonce = Nonce::<Aes256Gcm>::from_slice(&password_nonce_bytes);
-
-        let decrypted_password = match CryptoService::decrypt(&encrypted_password, &encryption_key, &password_nonce) {
-            Ok(decrypted) => decrypted,
             Err(e) => {
-                println!("Decryption failed: {}", e);
+                println!("{}", e);
                 return;
             }
-        };
-
-        if decrypted_password != password {
-            println!("Incorrect password. Please try again.");
-            return;
         }
 
         let state_data = json!({
@@ -253,7 +226,7 @@ impl AccountService {
         let private_key = hex::encode(wallet.signer().to_bytes());
 
         println!("Account Info for '{}':", account_name);
-        println!("Wallet Address: {:?}", wallet_address);
+        println!("Wallet Address: {}", checksum(&wallet_address));
         println!("Private Key: {}", private_key);
     }
 
@@ -282,7 +255,25 @@ impl AccountService {
         Some(wallet)
     }
 
-    fn get_password(prompt: &str) -> String {
+    pub fn verify_password(account_name: &str, password: &str) -> Result<bool, String> {
+        let account_file = Path::new(STORAGE_DIR).join(account_name).join("account_info.json");
+        let account_data = fs::read_to_string(&account_file).map_err(|e| format!("Failed to read account data: {}", e))?;
+        let account_json: Value =
+            serde_json::from_str(&account_data).map_err(|e| format!("Failed to parse account data: {}", e))?;
+        let field = |name: &str| account_json[name].as_str().ok_or(format!("Account data has no '{}'", name));
+
+        let encryption_key_bytes = hex::decode(field("encryption_key")?).map_err(|_| "Failed to decode encryption key")?;
+        let encryption_key = Key::<Aes256Gcm>::from_slice(&encryption_key_bytes);
+
+        let password_nonce_bytes = hex::decode(field("password_nonce")?).map_err(|_| "Failed to decode password nonce")?;
+        let password_nonce = Nonce::<Aes256Gcm>::from_slice(&password_nonce_bytes);
+
+        let decrypted_password = CryptoService::decrypt(field("encrypted_password")?, encryption_key, password_nonce)
+            .map_err(|e| format!("Decryption failed: {}", e))?;
+        Ok(decrypted_password == password)
+    }
+
+    pub fn get_password(prompt: &str) -> String {
         print!("{}", prompt);
         io::stdout().flush().unwrap();
 
@@ -0,0 +1,181 @@
+/*
+    Address validation - parsing user-supplied addresses and formatting addresses for output.
+    - Mixed-case input must match its EIP-55 checksum; all-lowercase or all-uppercase input carries no checksum and
+      is accepted with a warning.
+    - Recipient checks flag the zero address, the sender's own address and a token contract as the recipient of its
+      own tokens.
+    - Output uses the EIP-55 checksummed form.
+    - Recipients are classified by their code as accounts, accounts with EIP-7702 delegated code, contracts or token
+      contracts.
+*/
+use std::str::FromStr;
+use ethers::core::types::Address;
+use ethers::utils::to_checksum;
+
+pub fn checksum(address: &Address) -> String {
+    to_checksum(address, None)
+}
+
+/// Checksummed form of a stored address string; strings that are not addresses are returned unchanged.
+pub fn checksum_str(address: &str) -> String {
+    Address::from_str(address).map(|address| checksum(&address)).unwrap_or_else(|_| address.to_string())
+}
+
+/// Parses an address, rejecting mixed-case input whose EIP-55 checksum does not match. `kind` names the address in
+/// error messages, e.g. "destination".
+pub fn parse_address(input: &str, kind: &str) -> Result<Address, String> {
+    let input = input.trim();
+    let address = Address::from_str(input).map_err(|_| format!("Invalid {} address format", kind))?;
+    let digits = hex_digits(input);
+    if is_mixed_case(digits) && checksum(&address)[2..] != *digits {
+        return Err(format!(
+            "Invalid {} address {}: EIP-55 checksum mismatch, check the address for typos",
+            kind, input
+        ));
+    }
+    Ok(address)
+}
+
+/// Warning for input without a checksum, which cannot catch typos.
+pub fn checksum_warning(input: &str) -> Option<String> {
+    let input = input.trim();
+    let digits = hex_digits(input);
+    if is_mixed_case(digits) || !digits.chars().any(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let address = Address::from_str(input).ok()?;
+    Some(format!(
+        "{} has no EIP-55 checksum, so typos cannot be detected; the checksummed form is {}",
+        input,
+        checksum(&address)
+    ))
+}
+
+/// Warnings for recipients that are almost certainly a mistake.
+pub fn recipient_warnings(to: Address, from: Address, token: Option<Address>) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if to.is_zero() {
+        warnings.push("Recipient is the zero address; anything sent there is burned".to_string());
+    }
+    if to == from {
+        warnings.push("Recipient is your own address".to_string());
+    }
+    if token == Some(to) {
+        warnings.push("Recipient is the token contract itself; tokens sent to it are usually lost".to_string());
+    }
+    warnings
+}
+
+/// Prefix of the code of an account that delegates to a contract under EIP-7702.
+const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountKind {
+    Account,
+    Delegated,
+    Contract,
+    /// ERC-20 token contract with its symbol.
+    Token(String),
+}
+
+impl AccountKind {
+    /// Classifies by `eth_getCode`; token contracts are told apart by the caller.
+    pub fn from_code(code: &[u8]) -> Self {
+        if code.is_empty() {
+            AccountKind::Account
+        } else if code.len() == 23 && code.starts_with(&DELEGATION_PREFIX) {
+            AccountKind::Delegated
+        } else {
+            AccountKind::Contract
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            AccountKind::Account => "account (no code)".to_string(),
+            AccountKind::Delegated => "account with delegated code (EIP-7702)".to_string(),
+            AccountKind::Contract => "contract".to_string(),
+            AccountKind::Token(symbol) => format!("{} token contract", symbol),
+        }
+    }
+
+    /// Warning for sending the native token (`token` none) or an ERC-20 token to an account of this kind.
+    pub fn warning(&self, native_token: &str, token: Option<&str>) -> Option<String> {
+        match (self, token) {
+            (AccountKind::Token(symbol), _) => Some(format!(
+                "Recipient is the {} token contract; funds sent to a token contract are usually lost",
+                symbol
+            )),
+            (AccountKind::Contract, None) => Some(format!(
+                "Recipient is a contract; unless it is built to accept {}, the transfer reverts or the funds are stuck",
+                native_token
+            )),
+            (AccountKind::Contract, Some(symbol)) => Some(format!(
+                "Recipient is a contract; {} sent to a contract that does not expect it is usually lost",
+                symbol
+            )),
+            _ => None,
+        }
+    }
+}
+
+fn hex_digits(input: &str) -> &str {
+    input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")).unwrap_or(input)
+}
+
+fn is_mixed_case(digits: &str) -> bool {
+    digits.chars().any(|c| c.is_ascii_lowercase()) && digits.chars().any(|c| c.is_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECKSUMMED: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    #[test]
+    fn test_parse_address_checks_mixed_case() {
+        let address = parse_address(CHECKSUMMED, "destination").unwrap();
+        assert_eq!(checksum(&address), CHECKSUMMED);
+        assert_eq!(parse_address(&CHECKSUMMED.to_lowercase(), "destination").unwrap(), address);
+        assert_eq!(parse_address(&format!("0x{}", CHECKSUMMED[2..].to_uppercase()), "destination").unwrap(), address);
+
+        let typo = CHECKSUMMED.replace("5aA", "5Aa");
+        assert!(parse_address(&typo, "destination").unwrap_err().contains("checksum"));
+        assert_eq!(parse_address("0x1234", "token").unwrap_err(), "Invalid token address format");
+    }
+
+    #[test]
+    fn test_checksum_warning() {
+        assert!(checksum_warning(CHECKSUMMED).is_none());
+        assert!(checksum_warning(&CHECKSUMMED.to_lowercase()).unwrap().ends_with(CHECKSUMMED));
+        assert!(checksum_warning("0x0000000000000000000000000000000000000002").is_none());
+        assert_eq!(checksum_str("not an address"), "not an address");
+        assert_eq!(checksum_str(&CHECKSUMMED.to_lowercase()), CHECKSUMMED);
+    }
+
+    #[test]
+    fn test_account_kind() {
+        assert_eq!(AccountKind::from_code(&[]), AccountKind::Account);
+        let mut delegation = DELEGATION_PREFIX.to_vec();
+        delegation.extend_from_slice(Address::from_low_u64_be(5).as_bytes());
+        assert_eq!(AccountKind::from_code(&delegation), AccountKind::Delegated);
+        assert_eq!(AccountKind::from_code(&[0x60, 0x80, 0x60, 0x40]), AccountKind::Contract);
+
+        assert!(AccountKind::Account.warning("ETH", None).is_none());
+        assert!(AccountKind::Delegated.warning("ETH", Some("USDC")).is_none());
+        assert!(AccountKind::Contract.warning("ETH", None).unwrap().contains("accept ETH"));
+        assert!(AccountKind::Contract.warning("ETH", Some("USDC")).unwrap().starts_with("Recipient is a contract; USDC"));
+        assert!(AccountKind::Token("USDC".to_string()).warning("ETH", None).unwrap().contains("USDC token contract"));
+    }
+
+    #[test]
+    fn test_recipient_warnings() {
+        let from = Address::from_low_u64_be(1);
+        let token = Address::from_low_u64_be(2);
+        assert!(recipient_warnings(Address::from_low_u64_be(3), from, Some(token)).is_empty());
+        assert_eq!(recipient_warnings(Address::zero(), from, None).len(), 1);
+        assert_eq!(recipient_warnings(from, from, None), vec!["Recipient is your own address"]);
+        assert_eq!(recipient_warnings(token, from, Some(token)).len(), 1);
+    }
+}
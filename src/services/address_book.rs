@@ -0,0 +1,58 @@
+/*
+    AddressBook - optional labels for known addresses.
+    - Labels are read from storage/<account>/address_book.json.
+    - The file is a JSON object mapping a label to an address, e.g. {"treasury": "0x..."}.
+*/
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use ethers::core::types::Address;
+use crate::config::STORAGE_DIR;
+use crate::services::address::checksum;
+
+pub struct AddressBook {
+    labels: HashMap<Address, String>,
+}
+
+impl AddressBook {
+    pub fn load(account_name: &str) -> Self {
+        let path = Path::new(STORAGE_DIR).join(account_name).join("address_book.json");
+        let entries: HashMap<String, String> = fs::read_to_string(path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+
+        let labels = entries
+            .into_iter()
+            .filter_map(|(label, address)| Address::from_str(&address).ok().map(|address| (address, label)))
+            .collect();
+
+        AddressBook { labels }
+    }
+
+    pub fn label(&self, address: &Address) -> Option<&str> {
+        self.labels.get(address).map(|label| label.as_str())
+    }
+
+    pub fn resolve(&self, label: &str) -> Option<Address> {
+        self.labels
+            .iter()
+            .find(|(_, name)| name.as_str() == label)
+            .map(|(address, _)| *address)
+    }
+
+    pub fn describe(&self, address: &Address) -> String {
+        self.describe_with_name(address, None)
+    }
+
+    /// Like `describe`, also showing the address's ENS name when known.
+    pub fn describe_with_name(&self, address: &Address, ens_name: Option<&str>) -> String {
+        let names: Vec<&str> = self.label(address).into_iter().chain(ens_name).collect();
+        if names.is_empty() {
+            checksum(address)
+        } else {
+            format!("{} ({})", checksum(address), names.join(", "))
+        }
+    }
+}
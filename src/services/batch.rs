@@ -0,0 +1,254 @@
+/*
+    BatchService - CSV batch payouts.
+    - Rows are `recipient,amount[,token_address]`, amounts in base units (wei) as for `tx send`.
+    - Blank lines, `#` comments and a header row are skipped.
+    - Progress is kept next to the CSV file so a partially sent batch can be resumed. Rows are keyed by recipient,
+      amount, token and occurrence rather than line number, so editing other lines does not send a row again.
+    - Progress records the account and network it was made with; resuming with a different one is refused.
+    - Each row is marked as sent (nonce and signed hash) before it is broadcast; on resume, marked rows are checked on
+      chain so a transaction the node accepted is never paid twice.
+*/
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use ethers::core::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use crate::services::address::parse_address;
+
+#[derive(Debug, Clone)]
+pub struct BatchRow {
+    pub line: usize,
+    pub recipient: Address,
+    pub amount: U256,
+    pub token: Option<Address>,
+    /// How many earlier rows pay the same amount of the same asset to the same recipient.
+    pub occurrence: usize,
+}
+
+impl BatchRow {
+    pub fn key(&self) -> String {
+        let token = self.token.map(|token| format!("{:?}", token)).unwrap_or_default();
+        format!("{:?}:{}:{}:{}", self.recipient, self.amount, token, self.occurrence)
+    }
+}
+
+/// A row signed and about to be broadcast, or broadcast without a confirmed result.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SentRow {
+    pub nonce: u64,
+    pub hash: String,
+    pub raw_transaction: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct BatchProgress {
+    #[serde(default)]
+    pub account: Option<String>,
+    #[serde(default)]
+    pub network: Option<String>,
+    #[serde(default)]
+    pub chain_id: Option<u64>,
+    pub completed: HashMap<String, String>,
+    #[serde(default)]
+    pub sent: HashMap<String, SentRow>,
+}
+
+impl BatchProgress {
+    pub fn load(path: &str) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    /// Binds fresh progress to the account and network, and refuses progress recorded with different ones.
+    pub fn claim(&mut self, account: &str, network: &str, chain_id: u64, path: &str) -> Result<(), String> {
+        if self.completed.is_empty() && self.sent.is_empty() {
+            self.account = Some(account.to_string());
+            self.network = Some(network.to_string());
+            self.chain_id = Some(chain_id);
+            return Ok(());
+        }
+        match (&self.account, &self.network, self.chain_id) {
+            (Some(recorded_account), Some(recorded_network), Some(recorded_chain_id))
+                if recorded_account.eq_ignore_ascii_case(account)
+                    && recorded_network == network
+                    && recorded_chain_id == chain_id =>
+            {
+                Ok(())
+            }
+            (Some(recorded_account), Some(recorded_network), _) => Err(format!(
+                "Batch progress in {} was recorded by {} on '{}', not {} on '{}'; refusing to resume. \
+                Move the progress file away to send the batch from this account and network.",
+                path, recorded_account, recorded_network, account, network
+            )),
+            _ => Err(format!(
+                "Batch progress in {} does not record its account and network; refusing to resume. \
+                Check which rows were paid and move the progress file away before sending again.",
+                path
+            )),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+pub struct BatchService;
+
+impl BatchService {
+    pub fn progress_path(csv_path: &str) -> String {
+        format!("{}.progress.json", csv_path)
+    }
+
+    pub fn read_rows(csv_path: &str) -> Result<Vec<BatchRow>, Box<dyn Error>> {
+        if !Path::new(csv_path).exists() {
+            return Err(format!("Batch file '{}' not found", csv_path).into());
+        }
+        let content = fs::read_to_string(csv_path)?;
+        Self::parse_csv(&content).map_err(|errors| {
+            for error in &errors {
+                println!("  {}", error);
+            }
+            format!("Batch file has {} invalid row(s); nothing was sent", errors.len()).into()
+        })
+    }
+
+    pub fn parse_csv(content: &str) -> Result<Vec<BatchRow>, Vec<String>> {
+        let mut rows: Vec<BatchRow> = Vec::new();
+        let mut errors = Vec::new();
+
+        for (index, raw_line) in content.lines().enumerate() {
+            let line = index + 1;
+            let trimmed = raw_line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = trimmed.split(',').map(|field| field.trim()).collect();
+            if rows.is_empty() && errors.is_empty() && fields[0].eq_ignore_ascii_case("recipient") {
+                continue;
+            }
+
+            if fields.len() < 2 || fields.len() > 3 {
+                errors.push(format!("Line {}: expected `recipient,amount[,token_address]`", line));
+                continue;
+            }
+
+            let recipient = parse_address(fields[0], "recipient");
+            let amount = U256::from_dec_str(fields[1]);
+            let token = match fields.get(2) {
+                Some(token) if !token.is_empty() => parse_address(token, "token").map(Some),
+                _ => Ok(None),
+            };
+
+            match (recipient, amount, token) {
+                (Ok(recipient), Ok(amount), Ok(token)) if !amount.is_zero() => {
+                    let occurrence = rows
+                        .iter()
+                        .filter(|row| row.recipient == recipient && row.amount == amount && row.token == token)
+                        .count();
+                    rows.push(BatchRow { line, recipient, amount, token, occurrence });
+                }
+                (Err(e), _, _) => errors.push(format!("Line {}: {} ('{}')", line, e, fields[0])),
+                (_, Err(_), _) => errors.push(format!("Line {}: invalid amount '{}'", line, fields[1])),
+                (_, _, Err(e)) => errors.push(format!("Line {}: {} ('{}')", line, e, fields[2])),
+                _ => errors.push(format!("Line {}: amount must be greater than zero", line)),
+            }
+        }
+
+        if rows.is_empty() && errors.is_empty() {
+            errors.push("Batch file contains no rows".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(rows)
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn totals(rows: &[&BatchRow]) -> Vec<(Option<Address>, U256)> {
+        let mut totals: Vec<(Option<Address>, U256)> = Vec::new();
+        for row in rows {
+            match totals.iter_mut().find(|(token, _)| *token == row.token) {
+                Some((_, total)) => *total += row.amount,
+                None => totals.push((row.token, row.amount)),
+            }
+        }
+        totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const RECIPIENT: &str = "0x000000000000000000000000000000000000dEaD";
+    const TOKEN: &str = "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238";
+
+    #[test]
+    fn test_parse_csv() {
+        let content = format!(
+            "recipient,amount,token\n# monthly payouts\n{r},1000\n\n{r},250,{t}\n{r},750,{t}\n",
+            r = RECIPIENT,
+            t = TOKEN
+        );
+        let rows = BatchService::parse_csv(&content).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].line, 3);
+        assert_eq!(rows[0].token, None);
+        assert_eq!(rows[1].token, Some(Address::from_str(TOKEN).unwrap()));
+
+        let refs: Vec<&BatchRow> = rows.iter().collect();
+        let totals = BatchService::totals(&refs);
+        assert_eq!(totals, vec![(None, U256::from(1000)), (Some(Address::from_str(TOKEN).unwrap()), U256::from(1000))]);
+    }
+
+    #[test]
+    fn test_parse_csv_reports_every_invalid_row() {
+        let content = format!("0x1234,10\n{},abc\n{},0\n", RECIPIENT, RECIPIENT);
+        let errors = BatchService::parse_csv(&content).unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("Line 1"));
+
+        let typo = format!("{},10\n", TOKEN.replace("1c7D", "1C7d"));
+        assert!(BatchService::parse_csv(&typo).unwrap_err()[0].contains("checksum"));
+    }
+
+    #[test]
+    fn test_progress_without_sent_rows() {
+        let progress: BatchProgress = serde_json::from_str(r#"{"completed": {"3:0xdead:1000:": "0x01"}}"#).unwrap();
+        assert_eq!(progress.completed.len(), 1);
+        assert!(progress.sent.is_empty());
+    }
+
+    #[test]
+    fn test_row_keys_survive_edits_to_other_lines() {
+        let original = format!("{r},1000\n{r},1000\n{r},250,{t}\n", r = RECIPIENT, t = TOKEN);
+        let edited = format!("recipient,amount,token\n# paid on the 1st\n{r},1000\n\n{r},1000\n{r},250,{t}\n", r = RECIPIENT, t = TOKEN);
+        let keys = |content: &str| -> Vec<String> {
+            BatchService::parse_csv(content).unwrap().iter().map(|row| row.key()).collect()
+        };
+        assert_eq!(keys(&original), keys(&edited));
+        assert_ne!(keys(&original)[0], keys(&original)[1]);
+    }
+
+    #[test]
+    fn test_progress_is_bound_to_account_and_network() {
+        let mut progress = BatchProgress::default();
+        progress.claim(RECIPIENT, "sepolia", 11155111, "payouts.csv.progress.json").unwrap();
+        progress.completed.insert("row".to_string(), "0x01".to_string());
+
+        assert!(progress.claim(RECIPIENT, "sepolia", 11155111, "payouts.csv.progress.json").is_ok());
+        assert!(progress.claim(RECIPIENT, "mainnet", 1, "payouts.csv.progress.json").is_err());
+        assert!(progress.claim(TOKEN, "sepolia", 11155111, "payouts.csv.progress.json").is_err());
+
+        let mut legacy: BatchProgress = serde_json::from_str(r#"{"completed": {"3:0xdead:1000:": "0x01"}}"#).unwrap();
+        assert!(legacy.claim(RECIPIENT, "sepolia", 11155111, "payouts.csv.progress.json").is_err());
+    }
+}
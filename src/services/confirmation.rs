@@ -0,0 +1,115 @@
+/*
+    Confirmation - review screen shown before a transaction is signed and broadcast.
+    - Summarizes network, parties, amount and fee estimate in human units.
+    - Shows whether the recipient is an account or a contract and lists warnings about it (missing checksum, zero
+      address, own address, contract or token contract).
+    - Requires the user to type "yes" unless confirmation is skipped (--yes).
+*/
+use std::error::Error;
+use std::io::{self, Write};
+use ethers::core::types::{Address, U256};
+use crate::services::address::{checksum, AccountKind};
+use crate::services::address_book::AddressBook;
+use crate::services::units::format_units;
+
+pub struct AssetInfo {
+    pub symbol: String,
+    pub decimals: u32,
+    pub contract: Option<Address>,
+}
+
+pub struct TxSummary {
+    pub network_name: String,
+    pub chain_id: u64,
+    pub native_token: String,
+    pub from: Address,
+    pub to: Option<Address>,
+    /// ENS name the recipient was resolved from.
+    pub to_name: Option<String>,
+    /// Result of the recipient code check; none when it was not checked.
+    pub to_kind: Option<AccountKind>,
+    pub amount: U256,
+    pub asset: AssetInfo,
+    pub call: Option<String>,
+    pub gas_limit: U256,
+    pub fee_low: U256,
+    pub fee_high: U256,
+    pub warnings: Vec<String>,
+}
+
+impl TxSummary {
+    /// Fails when the asset's decimals are out of range, so the amount cannot be shown.
+    pub fn print(&self, address_book: &AddressBook) -> Result<(), String> {
+        println!("Please review the transaction:");
+        println!("  Network:      {} (Chain ID: {})", self.network_name, self.chain_id);
+        println!("  From:         {}", address_book.describe(&self.from));
+        match &self.to {
+            Some(to) => println!("  To:           {}", address_book.describe(to)),
+            None => println!("  To:           (contract creation)"),
+        }
+        if let Some(name) = &self.to_name {
+            println!("  ENS name:     {}", name);
+        }
+        if let Some(kind) = &self.to_kind {
+            println!("  Recipient:    {}", kind.describe());
+        }
+        println!("  Amount:       {} {}", format_units(self.amount, self.asset.decimals)?, self.asset.symbol);
+        match &self.asset.contract {
+            Some(contract) => println!("  Token:        {} ({})", self.asset.symbol, checksum(contract)),
+            None => println!("  Token:        {} (native)", self.asset.symbol),
+        }
+        if let Some(call) = &self.call {
+            println!("  Function:     {}", call);
+        }
+        println!("  Gas limit:    {}", self.gas_limit);
+        println!("  Fee estimate: {}", self.fee_range()?);
+        println!("  Total cost:   {}", self.total_cost()?);
+        for warning in &self.warnings {
+            println!("  WARNING: {}", warning);
+        }
+        Ok(())
+    }
+
+    pub fn confirm(&self, address_book: &AddressBook, skip_confirmation: bool) -> Result<(), Box<dyn Error>> {
+        self.print(address_book)?;
+        if skip_confirmation {
+            return Ok(());
+        }
+
+        confirm_prompt("Type 'yes' to sign and broadcast this transaction: ")
+    }
+
+    fn fee_range(&self) -> Result<String, String> {
+        let low = format_units(self.fee_low, 18)?;
+        let high = format_units(self.fee_high, 18)?;
+        if self.fee_low == self.fee_high {
+            Ok(format!("{} {}", high, self.native_token))
+        } else {
+            Ok(format!("{} - {} {}", low, high, self.native_token))
+        }
+    }
+
+    fn total_cost(&self) -> Result<String, String> {
+        Ok(match &self.asset.contract {
+            Some(_) => format!(
+                "{} {} + up to {} {}",
+                format_units(self.amount, self.asset.decimals)?,
+                self.asset.symbol,
+                format_units(self.fee_high, 18)?,
+                self.native_token
+            ),
+            None => format!("up to {} {}", format_units(self.amount + self.fee_high, 18)?, self.native_token),
+        })
+    }
+}
+
+pub fn confirm_prompt(prompt: &str) -> Result<(), Box<dyn Error>> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if answer.trim() != "yes" {
+        return Err("Transaction cancelled".into());
+    }
+    Ok(())
+}
@@ -0,0 +1,275 @@
+/*
+    ContractService - ABI helpers for arbitrary contract interactions.
+    - Functions are given as a signature, e.g. "deposit(uint256,address)" or "balanceOf(address)(uint256)",
+      or looked up by name or signature in an ABI file.
+    - ABI files may be a plain ABI array or a compiler artifact with an "abi" field.
+    - Arguments are encoded from their string form with ethers' ABI encoder.
+    - ERC-20 `transfer` and `approve` calldata is decoded so reviews and policy checks see the token amounts.
+*/
+use std::error::Error;
+use std::fs;
+use ethers::abi::token::{LenientTokenizer, Tokenizer};
+use ethers::abi::{Abi, AbiParser, Function, Param, Token};
+use ethers::core::types::{Address, Bytes, U256};
+use serde_json::{json, Value};
+use crate::services::address::checksum;
+
+const ERC20_TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+const ERC20_APPROVE_SELECTOR: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenCall {
+    Transfer { to: Address, amount: U256 },
+    Approve { spender: Address, amount: U256 },
+}
+
+pub struct ContractService;
+
+impl ContractService {
+    pub fn load_abi(path: &str) -> Result<Abi, Box<dyn Error>> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read ABI file '{}': {}", path, e))?;
+        Self::parse_abi(&data).map_err(|e| format!("Failed to parse ABI file '{}': {}", path, e).into())
+    }
+
+    pub fn parse_abi(data: &str) -> Result<Abi, Box<dyn Error>> {
+        let json: serde_json::Value = serde_json::from_str(data)?;
+        let abi = match json.get("abi") {
+            Some(abi) => abi.clone(),
+            None => json,
+        };
+        Ok(serde_json::from_value(abi)?)
+    }
+
+    pub fn resolve_function(signature: &str, abi_path: Option<&str>) -> Result<Function, Box<dyn Error>> {
+        match abi_path {
+            Some(path) => Self::find_function(&Self::load_abi(path)?, signature),
+            None => AbiParser::default()
+                .parse_function(signature)
+                .map_err(|e| format!("Invalid function signature '{}': {}", signature, e).into()),
+        }
+    }
+
+    pub fn find_function(abi: &Abi, signature: &str) -> Result<Function, Box<dyn Error>> {
+        let signature = signature.trim();
+        if signature.contains('(') {
+            let wanted = signature.replace(' ', "");
+            return abi
+                .functions()
+                .find(|function| Self::input_signature(function) == wanted)
+                .cloned()
+                .ok_or_else(|| format!("Function '{}' not found in ABI", signature).into());
+        }
+
+        let candidates = abi.functions_by_name(signature).map_err(|_| format!("Function '{}' not found in ABI", signature))?;
+        match candidates.as_slice() {
+            [function] => Ok(function.clone()),
+            _ => Err(format!(
+                "Function '{}' is overloaded; use the full signature, one of: {}",
+                signature,
+                candidates.iter().map(Self::input_signature).collect::<Vec<_>>().join(", ")
+            ).into()),
+        }
+    }
+
+    pub fn load_bytecode(path: &str) -> Result<Bytes, Box<dyn Error>> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read bytecode file '{}': {}", path, e))?;
+        let hex_code = match serde_json::from_str::<serde_json::Value>(&data) {
+            Ok(artifact) => match &artifact["bytecode"] {
+                Value::String(code) => code.clone(),
+                Value::Object(code) => code.get("object").and_then(|code| code.as_str()).unwrap_or_default().to_string(),
+                _ => return Err(format!("No bytecode found in '{}'", path).into()),
+            },
+            Err(_) => data.split_whitespace().collect(),
+        };
+
+        let bytecode: Bytes = hex_code.parse().map_err(|_| format!("Invalid bytecode hex in '{}'", path))?;
+        if bytecode.is_empty() {
+            return Err(format!("Bytecode in '{}' is empty", path).into());
+        }
+        Ok(bytecode)
+    }
+
+    pub fn encode_constructor(abi: &Abi, bytecode: Bytes, args: &[String]) -> Result<(Bytes, String), Box<dyn Error>> {
+        let call = format!("constructor({})", args.join(", "));
+        match abi.constructor() {
+            Some(constructor) => {
+                let tokens = Self::tokenize_params("constructor", &constructor.inputs, args)?;
+                Ok((constructor.encode_input(bytecode.to_vec(), &tokens)?.into(), call))
+            }
+            None if args.is_empty() => Ok((bytecode, call)),
+            None => Err("ABI has no constructor, but constructor arguments were given".into()),
+        }
+    }
+
+    pub fn tokenize_args(function: &Function, args: &[String]) -> Result<Vec<Token>, Box<dyn Error>> {
+        Self::tokenize_params(&Self::input_signature(function), &function.inputs, args)
+    }
+
+    fn tokenize_params(name: &str, params: &[Param], args: &[String]) -> Result<Vec<Token>, Box<dyn Error>> {
+        if params.len() != args.len() {
+            return Err(format!(
+                "'{}' expects {} argument(s), got {}",
+                name,
+                params.len(),
+                args.len()
+            ).into());
+        }
+
+        params
+            .iter()
+            .zip(args)
+            .map(|(param, arg)| {
+                LenientTokenizer::tokenize(&param.kind, arg)
+                    .map_err(|e| format!("Invalid value '{}' for {} argument: {}", arg, param.kind, e).into())
+            })
+            .collect()
+    }
+
+    pub fn encode_call(function: &Function, args: &[String]) -> Result<Bytes, Box<dyn Error>> {
+        let tokens = Self::tokenize_args(function, args)?;
+        Ok(function.encode_input(&tokens)?.into())
+    }
+
+    pub fn input_signature(function: &Function) -> String {
+        let inputs: Vec<String> = function.inputs.iter().map(|param| param.kind.to_string()).collect();
+        format!("{}({})", function.name, inputs.join(","))
+    }
+
+    pub fn describe_call(function: &Function, args: &[String]) -> String {
+        format!("{}({})", function.name, args.join(", "))
+    }
+
+    pub fn output_name(function: &Function, index: usize) -> String {
+        match function.outputs.get(index) {
+            Some(param) if !param.name.is_empty() => param.name.clone(),
+            _ => index.to_string(),
+        }
+    }
+
+    pub fn format_token(token: &Token) -> String {
+        match token {
+            Token::Address(address) => checksum(address),
+            Token::FixedBytes(bytes) | Token::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+            Token::Int(value) => ethers::core::types::I256::from_raw(*value).to_string(),
+            Token::Uint(value) => value.to_string(),
+            Token::Bool(value) => value.to_string(),
+            Token::String(value) => value.clone(),
+            Token::FixedArray(tokens) | Token::Array(tokens) => {
+                format!("[{}]", tokens.iter().map(Self::format_token).collect::<Vec<_>>().join(", "))
+            }
+            Token::Tuple(tokens) => {
+                format!("({})", tokens.iter().map(Self::format_token).collect::<Vec<_>>().join(", "))
+            }
+        }
+    }
+
+    /// Decodes ERC-20 `transfer(address,uint256)` and `approve(address,uint256)` calldata. Only exact encodings
+    /// are accepted: no trailing bytes and a zero-padded address word.
+    pub fn decode_token_call(data: &[u8]) -> Option<TokenCall> {
+        if data.len() != 4 + 32 * 2 || data[4..16].iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        let address = Address::from_slice(&data[16..36]);
+        let amount = U256::from_big_endian(&data[36..68]);
+        let selector = &data[..4];
+        if selector == ERC20_TRANSFER_SELECTOR {
+            Some(TokenCall::Transfer { to: address, amount })
+        } else if selector == ERC20_APPROVE_SELECTOR {
+            Some(TokenCall::Approve { spender: address, amount })
+        } else {
+            None
+        }
+    }
+
+    pub fn token_to_json(token: &Token) -> Value {
+        match token {
+            Token::Bool(value) => json!(value),
+            Token::FixedArray(tokens) | Token::Array(tokens) | Token::Tuple(tokens) => {
+                Value::Array(tokens.iter().map(Self::token_to_json).collect())
+            }
+            _ => json!(Self::format_token(token)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_call_from_signature() {
+        let function = ContractService::resolve_function("deposit(uint256,address)", None).unwrap();
+        let args = vec!["100".to_string(), "0x000000000000000000000000000000000000dEaD".to_string()];
+        let data = ContractService::encode_call(&function, &args).unwrap();
+        assert_eq!(&data[..4], &function.short_signature());
+        assert_eq!(data.len(), 4 + 32 * 2);
+        assert_eq!(data[35], 100);
+    }
+
+    #[test]
+    fn test_encode_call_checks_argument_count() {
+        let function = ContractService::resolve_function("deposit(uint256,address)", None).unwrap();
+        assert!(ContractService::encode_call(&function, &["1".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_decode_and_format_outputs() {
+        let function = ContractService::resolve_function("getReserves()(uint112,uint112,int32)", None).unwrap();
+        let encoded = ethers::abi::encode(&[
+            Token::Uint(1000.into()),
+            Token::Uint(2000.into()),
+            Token::Int(ethers::core::types::I256::from(-5).into_raw()),
+        ]);
+        let tokens = function.decode_output(&encoded).unwrap();
+        let formatted: Vec<String> = tokens.iter().map(ContractService::format_token).collect();
+        assert_eq!(formatted, vec!["1000", "2000", "-5"]);
+        assert_eq!(ContractService::token_to_json(&Token::Bool(true)), json!(true));
+    }
+
+    #[test]
+    fn test_encode_constructor_appends_arguments() {
+        let abi = ContractService::parse_abi(
+            r#"[{"type": "constructor", "inputs": [{"name": "owner", "type": "address"}], "stateMutability": "nonpayable"}]"#,
+        ).unwrap();
+        let bytecode = Bytes::from(vec![0x60, 0x80]);
+        let args = vec!["0x000000000000000000000000000000000000dEaD".to_string()];
+        let (data, call) = ContractService::encode_constructor(&abi, bytecode, &args).unwrap();
+        assert_eq!(data.len(), 2 + 32);
+        assert_eq!(&data[..2], &[0x60, 0x80]);
+        assert_eq!(call, "constructor(0x000000000000000000000000000000000000dEaD)");
+    }
+
+    #[test]
+    fn test_find_function_in_artifact() {
+        let artifact = r#"{"abi": [
+            {"type": "function", "name": "store", "inputs": [{"name": "v", "type": "uint256"}], "outputs": [], "stateMutability": "nonpayable"},
+            {"type": "function", "name": "store", "inputs": [{"name": "v", "type": "string"}], "outputs": [], "stateMutability": "nonpayable"}
+        ]}"#;
+        let abi = ContractService::parse_abi(artifact).unwrap();
+        assert!(ContractService::find_function(&abi, "store").is_err());
+        let function = ContractService::find_function(&abi, "store(string)").unwrap();
+        assert_eq!(function.inputs[0].kind.to_string(), "string");
+    }
+
+    #[test]
+    fn test_decode_token_call() {
+        let function = ContractService::resolve_function("transfer(address,uint256)", None).unwrap();
+        let args = vec!["0x000000000000000000000000000000000000dEaD".to_string(), "500".to_string()];
+        let data = ContractService::encode_call(&function, &args).unwrap();
+        assert_eq!(
+            ContractService::decode_token_call(&data),
+            Some(TokenCall::Transfer { to: Address::from_low_u64_be(0xdead), amount: U256::from(500) })
+        );
+
+        let mut trailing = data.to_vec();
+        trailing.push(0);
+        assert!(ContractService::decode_token_call(&trailing).is_none());
+        let mut dirty = data.to_vec();
+        dirty[4] = 1;
+        assert!(ContractService::decode_token_call(&dirty).is_none());
+
+        let function = ContractService::resolve_function("approve(address,uint256)", None).unwrap();
+        let data = ContractService::encode_call(&function, &args).unwrap();
+        assert!(matches!(ContractService::decode_token_call(&data), Some(TokenCall::Approve { .. })));
+    }
+}
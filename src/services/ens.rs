@@ -0,0 +1,86 @@
+/*
+    EnsService - resolving ENS names to addresses and addresses back to their primary names.
+    - The registry is configured per network (NetworkInfo::ens_registry); networks without one do not resolve names.
+    - Names are lowercased before resolution; full UTS-46 normalization is not performed.
+    - A reverse lookup is only used when the name resolves back to the same address.
+*/
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+use ethers::core::types::Address;
+use ethers::providers::{Http, Middleware, Provider};
+use crate::services::network::NetworkInfo;
+
+pub struct EnsService;
+
+impl EnsService {
+    /// Whether the input looks like an ENS name rather than a hex address.
+    pub fn is_name(input: &str) -> bool {
+        let input = input.trim();
+        !input.starts_with("0x")
+            && input.contains('.')
+            && input.split('.').all(|label| !label.is_empty() && !label.contains(char::is_whitespace))
+    }
+
+    pub fn normalize(name: &str) -> String {
+        name.trim().to_lowercase()
+    }
+
+    pub fn registry(network: &NetworkInfo) -> Option<Address> {
+        network.ens_registry.as_deref().and_then(|registry| Address::from_str(registry).ok())
+    }
+
+    pub async fn resolve(provider: &Provider<Http>, registry: Address, name: &str) -> Result<Address, Box<dyn Error>> {
+        let name = Self::normalize(name);
+        let address = provider
+            .clone()
+            .ens(registry)
+            .resolve_name(&name)
+            .await
+            .map_err(|e| format!("Could not resolve ENS name '{}': {}", name, e))?;
+        if address.is_zero() {
+            return Err(format!("ENS name '{}' has no address record", name).into());
+        }
+        Ok(address)
+    }
+
+    pub async fn lookup(provider: &Provider<Http>, registry: Address, address: Address) -> Option<String> {
+        provider.clone().ens(registry).lookup_address(address).await.ok()
+    }
+
+    /// Primary names of the given addresses; addresses without a verified name are left out.
+    pub async fn lookup_all(
+        provider: &Provider<Http>,
+        registry: Address,
+        addresses: impl IntoIterator<Item = Address>,
+    ) -> HashMap<Address, String> {
+        let mut names = HashMap::new();
+        let mut seen = Vec::new();
+        for address in addresses {
+            if seen.contains(&address) {
+                continue;
+            }
+            seen.push(address);
+            if let Some(name) = Self::lookup(provider, registry, address).await {
+                names.insert(address, name);
+            }
+        }
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_name() {
+        assert!(EnsService::is_name("vitalik.eth"));
+        assert!(EnsService::is_name(" pay.Alice.eth "));
+        assert!(!EnsService::is_name("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"));
+        assert!(!EnsService::is_name("treasury"));
+        assert!(!EnsService::is_name("vitalik."));
+        assert!(!EnsService::is_name("a..eth"));
+        assert_eq!(EnsService::normalize(" Vitalik.ETH "), "vitalik.eth");
+    }
+}
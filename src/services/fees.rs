@@ -0,0 +1,235 @@
+/*
+    FeeService - slow/normal/fast fee presets from eth_feeHistory.
+    - The priority fee of a preset is the median, over recent non-empty blocks, of the reward at its percentile
+      (config::FEE_REWARD_PERCENTILES).
+    - The max fee adds headroom for base fee growth: 12.5% per block (the protocol maximum) for 1, 2 or 6 blocks,
+      one block more when the base fee is trending up.
+    - Chains without a base fee get legacy presets derived from eth_gasPrice.
+*/
+use std::fmt;
+use std::str::FromStr;
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::core::types::{Eip1559TransactionRequest, FeeHistory, U256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeSpeed {
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl FeeSpeed {
+    pub const ALL: [FeeSpeed; 3] = [FeeSpeed::Slow, FeeSpeed::Normal, FeeSpeed::Fast];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeeSpeed::Slow => "slow",
+            FeeSpeed::Normal => "normal",
+            FeeSpeed::Fast => "fast",
+        }
+    }
+
+    fn percentile_index(&self) -> usize {
+        match self {
+            FeeSpeed::Slow => 0,
+            FeeSpeed::Normal => 1,
+            FeeSpeed::Fast => 2,
+        }
+    }
+
+    fn headroom_blocks(&self) -> u32 {
+        match self {
+            FeeSpeed::Slow => 1,
+            FeeSpeed::Normal => 2,
+            FeeSpeed::Fast => 6,
+        }
+    }
+
+    fn legacy_multiplier_percent(&self) -> u64 {
+        match self {
+            FeeSpeed::Slow => 100,
+            FeeSpeed::Normal => 110,
+            FeeSpeed::Fast => 125,
+        }
+    }
+}
+
+impl FromStr for FeeSpeed {
+    type Err = String;
+
+    fn from_str(speed: &str) -> Result<Self, Self::Err> {
+        match speed {
+            "slow" => Ok(FeeSpeed::Slow),
+            "normal" => Ok(FeeSpeed::Normal),
+            "fast" => Ok(FeeSpeed::Fast),
+            _ => Err(format!("Unknown speed '{}' (expected slow, normal or fast)", speed)),
+        }
+    }
+}
+
+impl fmt::Display for FeeSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeEstimate {
+    pub speed: FeeSpeed,
+    /// Expected base fee of the next block; zero for legacy presets.
+    pub base_fee: U256,
+    pub max_priority_fee: U256,
+    pub max_fee: U256,
+}
+
+impl FeeEstimate {
+    pub fn is_legacy(&self) -> bool {
+        self.base_fee.is_zero()
+    }
+
+    /// Fee per gas expected to be paid if the base fee does not change.
+    pub fn expected_fee_per_gas(&self) -> U256 {
+        if self.is_legacy() {
+            return self.max_fee;
+        }
+        (self.base_fee + self.max_priority_fee).min(self.max_fee)
+    }
+}
+
+pub struct FeeService;
+
+impl FeeService {
+    /// Returns whether the base fee is trending up, or none when the chain has no base fee.
+    pub fn base_fee_rising(history: &FeeHistory) -> Option<bool> {
+        let (next_base_fee, past) = history.base_fee_per_gas.split_last()?;
+        if next_base_fee.is_zero() || past.is_empty() {
+            return None;
+        }
+        let average = past.iter().fold(U256::zero(), |sum, fee| sum + fee) / past.len();
+        Some(*next_base_fee > average)
+    }
+
+    pub fn estimate(history: &FeeHistory, speed: FeeSpeed) -> Option<FeeEstimate> {
+        let rising = Self::base_fee_rising(history)?;
+        let base_fee = *history.base_fee_per_gas.last()?;
+
+        let mut rewards: Vec<U256> = history
+            .reward
+            .iter()
+            .zip(history.gas_used_ratio.iter().chain(std::iter::repeat(&1.0)))
+            .filter(|(_, gas_used_ratio)| **gas_used_ratio > 0.0)
+            .filter_map(|(rewards, _)| rewards.get(speed.percentile_index()).copied())
+            .collect();
+        rewards.sort();
+        let max_priority_fee = rewards.get(rewards.len() / 2).copied().unwrap_or_default();
+
+        let blocks = speed.headroom_blocks() + u32::from(rising);
+        let max_base_fee = (0..blocks).fold(base_fee, |fee, _| (fee * 9 + 7) / 8);
+
+        Some(FeeEstimate {
+            speed,
+            base_fee,
+            max_priority_fee,
+            max_fee: max_base_fee + max_priority_fee,
+        })
+    }
+
+    pub fn legacy_estimate(gas_price: U256, speed: FeeSpeed) -> FeeEstimate {
+        let price = gas_price * speed.legacy_multiplier_percent() / 100;
+        FeeEstimate {
+            speed,
+            base_fee: U256::zero(),
+            max_priority_fee: price,
+            max_fee: price,
+        }
+    }
+
+    /// Applies the estimate to a transaction, turning it into an EIP-1559 transaction unless the estimate is legacy.
+    pub fn apply(tx: &TypedTransaction, estimate: &FeeEstimate) -> TypedTransaction {
+        if estimate.is_legacy() {
+            let mut tx = tx.clone();
+            tx.set_gas_price(estimate.max_fee);
+            return tx;
+        }
+
+        let mut request = Eip1559TransactionRequest::new()
+            .value(tx.value().copied().unwrap_or_default())
+            .data(tx.data().cloned().unwrap_or_default())
+            .max_fee_per_gas(estimate.max_fee)
+            .max_priority_fee_per_gas(estimate.max_priority_fee);
+        request.to = tx.to().cloned();
+        request.from = tx.from().copied();
+        request.nonce = tx.nonce().copied();
+        request.gas = tx.gas().copied();
+        request.chain_id = tx.chain_id();
+        request.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::core::types::{Address, TransactionRequest};
+
+    fn gwei(value: u64) -> U256 {
+        U256::from(value) * U256::exp10(9)
+    }
+
+    fn history(base_fees: &[u64], rewards: &[[u64; 3]]) -> FeeHistory {
+        FeeHistory {
+            base_fee_per_gas: base_fees.iter().map(|fee| gwei(*fee)).collect(),
+            gas_used_ratio: vec![0.5; rewards.len()],
+            oldest_block: U256::from(100),
+            reward: rewards.iter().map(|reward| reward.iter().map(|fee| gwei(*fee)).collect()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_estimates_follow_percentiles_and_trend() {
+        let flat = history(&[16, 16, 16, 16], &[[1, 2, 5], [1, 3, 6], [2, 2, 4]]);
+        let slow = FeeService::estimate(&flat, FeeSpeed::Slow).unwrap();
+        assert_eq!(slow.base_fee, gwei(16));
+        assert_eq!(slow.max_priority_fee, gwei(1));
+        assert_eq!(slow.max_fee, gwei(18) + gwei(1));
+        let fast = FeeService::estimate(&flat, FeeSpeed::Fast).unwrap();
+        assert_eq!(fast.max_priority_fee, gwei(5));
+        assert!(fast.max_fee > FeeService::estimate(&flat, FeeSpeed::Normal).unwrap().max_fee);
+        assert_eq!(slow.expected_fee_per_gas(), gwei(17));
+
+        let rising = history(&[10, 12, 14, 16], &[[1, 2, 5], [1, 3, 6], [2, 2, 4]]);
+        assert_eq!(FeeService::base_fee_rising(&rising), Some(true));
+        let slow_rising = FeeService::estimate(&rising, FeeSpeed::Slow).unwrap();
+        assert_eq!(slow_rising.max_fee, gwei(16) * 81 / 64 + gwei(1));
+    }
+
+    #[test]
+    fn test_chain_without_base_fee_is_legacy() {
+        let legacy = history(&[0, 0, 0], &[[0, 0, 0], [0, 0, 0]]);
+        assert!(FeeService::estimate(&legacy, FeeSpeed::Normal).is_none());
+
+        let estimate = FeeService::legacy_estimate(gwei(10), FeeSpeed::Normal);
+        assert!(estimate.is_legacy());
+        assert_eq!(estimate.max_fee, gwei(11));
+    }
+
+    #[test]
+    fn test_apply_converts_to_eip1559() {
+        let tx: TypedTransaction = TransactionRequest::pay(Address::from_low_u64_be(2), 5)
+            .from(Address::from_low_u64_be(1))
+            .gas_price(gwei(1))
+            .into();
+        let estimate = FeeService::estimate(&history(&[16, 16], &[[1, 2, 5]]), FeeSpeed::Normal).unwrap();
+
+        let applied = FeeService::apply(&tx, &estimate);
+        match &applied {
+            TypedTransaction::Eip1559(inner) => {
+                assert_eq!(inner.max_fee_per_gas, Some(estimate.max_fee));
+                assert_eq!(inner.max_priority_fee_per_gas, Some(gwei(2)));
+            }
+            _ => panic!("expected an EIP-1559 transaction"),
+        }
+        assert_eq!(applied.value(), Some(&U256::from(5)));
+        assert_eq!(applied.from(), Some(&Address::from_low_u64_be(1)));
+        assert_eq!("normal".parse::<FeeSpeed>(), Ok(FeeSpeed::Normal));
+    }
+}
@@ -0,0 +1,812 @@
+/*
+    HistoryService - per-account, per-network transaction history.
+    - Stored in storage/<account>/<network>/tx_history.json as `{ "version": 2, "transactions": [...] }`.
+    - Version 1 files (a bare array of transactions) are upgraded on load; fields that were never recorded stay empty.
+    - Timestamps are unix seconds (UTC). Status starts as pending and is refreshed from receipts by `tx history`.
+*/
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::core::types::{Address, TransactionReceipt, H256, U256, U64};
+use serde::{Deserialize, Serialize};
+use crate::services::address::checksum;
+use crate::services::network::NetworkInfo;
+use crate::services::nft::NftService;
+use crate::services::units::{format_units, parse_units, MAX_DECIMALS};
+
+pub const HISTORY_VERSION: u32 = 2;
+
+pub const EXPORT_COLUMNS: [&str; 14] = [
+    "network",
+    "chain_id",
+    "timestamp",
+    "hash",
+    "status",
+    "type",
+    "direction",
+    "counterparty",
+    "asset",
+    "asset_contract",
+    "token_id",
+    "amount",
+    "fee_paid",
+    "fee_asset",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TxStatus {
+    Pending,
+    Success,
+    Failed,
+    Replaced,
+    Dropped,
+    #[default]
+    Unknown,
+}
+
+impl TxStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TxStatus::Pending => "pending",
+            TxStatus::Success => "success",
+            TxStatus::Failed => "failed",
+            TxStatus::Replaced => "replaced",
+            TxStatus::Dropped => "dropped",
+            TxStatus::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredTransaction {
+    #[serde(rename = "type")]
+    pub tx_type: String,
+    pub from: String,
+    pub to: Option<String>,
+    pub gas: String,
+    pub gas_price: String,
+    pub value: String,
+    pub token_value: Option<String>,
+    pub hash: Option<String>,
+    pub nonce: Option<String>,
+    pub kind: Option<String>,
+    pub contract_address: Option<String>,
+    pub token_id: Option<String>,
+    pub collection: Option<String>,
+    pub chain_id: Option<u64>,
+    pub token_contract: Option<String>,
+    pub token_symbol: Option<String>,
+    pub token_decimals: Option<u32>,
+    pub timestamp: Option<u64>,
+    #[serde(default)]
+    pub status: TxStatus,
+    pub fee_paid: Option<String>,
+}
+
+impl StoredTransaction {
+    pub fn sender_address(&self) -> Option<Address> {
+        Address::from_str(&self.from).ok()
+    }
+
+    pub fn to_address(&self) -> Option<Address> {
+        self.to.as_deref().and_then(|to| Address::from_str(to).ok())
+    }
+
+    pub fn gas_as_u256(&self) -> U256 {
+        U256::from_str_radix(self.gas.trim_start_matches("0x"), 16).unwrap_or_default()
+    }
+
+    pub fn gas_price_as_u256(&self) -> U256 {
+        U256::from_str_radix(self.gas_price.trim_start_matches("0x"), 16).unwrap_or_default()
+    }
+
+    pub fn value_as_u256(&self) -> U256 {
+        U256::from_str_radix(self.value.trim_start_matches("0x"), 16).unwrap_or_default()
+    }
+
+    pub fn token_value_as_u256(&self) -> Option<U256> {
+        self.token_value.as_deref().and_then(|v| U256::from_dec_str(v).ok())
+    }
+
+    pub fn nonce_as_u256(&self) -> Option<U256> {
+        self.nonce.as_deref().and_then(|n| U256::from_str_radix(n.trim_start_matches("0x"), 16).ok())
+    }
+
+    pub fn hash_as_h256(&self) -> Option<H256> {
+        self.hash.as_deref().and_then(|h| H256::from_str(h).ok())
+    }
+
+    pub fn hash_matches(&self, hash: &H256) -> bool {
+        self.hash_as_h256().as_ref() == Some(hash)
+    }
+
+    pub fn token_contract_address(&self) -> Option<Address> {
+        self.token_contract.as_deref().and_then(|token| Address::from_str(token).ok())
+    }
+
+    pub fn fee_paid_as_u256(&self) -> Option<U256> {
+        self.fee_paid.as_deref().and_then(|fee| U256::from_dec_str(fee).ok())
+    }
+
+    /// Records the outcome of a mined transaction: status, fee paid and, for deployments, the contract address.
+    pub fn apply_receipt(&mut self, receipt: &TransactionReceipt) {
+        self.status = if receipt.status == Some(U64::zero()) { TxStatus::Failed } else { TxStatus::Success };
+        if let (Some(gas_used), Some(gas_price)) = (receipt.gas_used, receipt.effective_gas_price) {
+            self.fee_paid = Some((gas_used * gas_price).to_string());
+        }
+        if let Some(contract_address) = receipt.contract_address {
+            self.contract_address = Some(format!("{:?}", contract_address));
+        }
+    }
+
+    pub fn direction(&self, account: Address) -> &'static str {
+        let outgoing = self.sender_address() == Some(account);
+        let incoming = self.to_address() == Some(account);
+        match (outgoing, incoming) {
+            (true, true) => "self",
+            (true, false) => "out",
+            _ => "in",
+        }
+    }
+
+    pub fn counterparty(&self, account: Address) -> Option<Address> {
+        match self.direction(account) {
+            "in" => self.sender_address(),
+            "self" => Some(account),
+            _ => self
+                .to_address()
+                .or_else(|| self.contract_address.as_deref().and_then(|address| Address::from_str(address).ok())),
+        }
+    }
+
+    /// Token decimals, when recorded and small enough to scale an amount by.
+    pub fn known_token_decimals(&self) -> Option<u32> {
+        self.token_decimals.filter(|decimals| *decimals <= MAX_DECIMALS)
+    }
+
+    /// Returns the transferred amount in base units and the decimals of its asset.
+    pub fn asset_base_amount(&self) -> (U256, u32) {
+        match (&self.collection, &self.token_contract, self.token_value_as_u256()) {
+            (Some(_), _, Some(token_value)) => (token_value, 0),
+            (None, Some(_), Some(token_value)) => (token_value, self.known_token_decimals().unwrap_or(0)),
+            _ => (self.value_as_u256(), 18),
+        }
+    }
+
+    /// Returns the asset symbol, its contract (none for the native token) and the amount in human units.
+    pub fn asset_amount(&self, native_token: &str) -> (String, Option<String>, String) {
+        let (amount, decimals) = self.asset_base_amount();
+        // The decimals are in range here, so the fallback to base units is never shown.
+        let amount = format_units(amount, decimals).unwrap_or_else(|_| amount.to_string());
+        match (&self.collection, &self.token_contract, self.token_value_as_u256()) {
+            (Some(collection), _, Some(_)) => ("NFT".to_string(), Some(collection.clone()), amount),
+            (None, Some(token), Some(_)) => {
+                (self.token_symbol.clone().unwrap_or_else(|| token.clone()), Some(token.clone()), amount)
+            }
+            _ => (native_token.to_string(), None, amount),
+        }
+    }
+
+    pub fn from_typed_transaction(tx: &TypedTransaction, hash: H256) -> Self {
+        let tx_type = match tx {
+            TypedTransaction::Legacy(_) => "Legacy",
+            TypedTransaction::Eip2930(_) => "Eip2930",
+            TypedTransaction::Eip1559(_) => "Eip1559",
+        }
+            .to_string();
+
+        let from = tx.from().map(|addr| format!("{:?}", addr)).unwrap_or_default();
+
+        let nft_transfer = tx.data().and_then(|data| NftService::decode_transfer(data));
+
+        let kind = match (tx.to(), tx.data()) {
+            (None, _) => "deploy",
+            (Some(_), Some(_)) if nft_transfer.is_some() => "nft_transfer",
+            (Some(_), Some(data)) if data.len() >= 68 && data.starts_with(&[0xa9, 0x05, 0x9c, 0xbb]) => "token_transfer",
+            (Some(_), Some(data)) if data.len() >= 68 && data.starts_with(&[0x09, 0x5e, 0xa7, 0xb3]) => "token_approval",
+            (Some(_), Some(data)) if !data.is_empty() => "contract_call",
+            _ => "transfer",
+        };
+
+        let (to, token_value) = if let Some(transfer) = &nft_transfer {
+            (Some(format!("{:?}", transfer.to)), Some(transfer.amount.to_string()))
+        } else if let Some(input_data) = tx.data() {
+            if input_data.len() >= 68 && input_data.starts_with(&[0xa9, 0x05, 0x9c, 0xbb]) {
+                let recipient_address = Address::from_slice(&input_data[16..36]);
+                let token_amount = U256::from_big_endian(&input_data[36..68]);
+                (Some(format!("{:?}", recipient_address)), Some(token_amount.to_string()))
+            } else {
+                (tx.to().map(|to| format!("{:?}", to)), None)
+            }
+        } else {
+            (tx.to().map(|to| format!("{:?}", to)), None)
+        };
+
+        let token_contract = match kind {
+            "token_transfer" | "token_approval" => tx.to_addr().map(|token| format!("{:?}", token)),
+            _ => None,
+        };
+
+        StoredTransaction {
+            tx_type,
+            from,
+            to,
+            gas: tx.gas().map(|g| format!("{:#x}", g)).unwrap_or_default(),
+            gas_price: tx.gas_price().map(|gp| format!("{:#x}", gp)).unwrap_or_default(),
+            value: tx.value().map(|v| format!("{:#x}", v)).unwrap_or("0x0".to_string()),
+            token_value,
+            hash: Some(format!("{:#x}", hash)),
+            nonce: tx.nonce().map(|n| format!("{:#x}", n)),
+            kind: Some(kind.to_string()),
+            contract_address: None,
+            token_id: nft_transfer.as_ref().map(|transfer| transfer.token_id.to_string()),
+            collection: nft_transfer.as_ref().and(tx.to_addr()).map(|collection| format!("{:?}", collection)),
+            chain_id: tx.chain_id().map(|id| id.as_u64()),
+            token_contract,
+            token_symbol: None,
+            token_decimals: None,
+            timestamp: Some(HistoryService::now()),
+            status: TxStatus::Pending,
+            fee_paid: None,
+        }
+    }
+}
+
+/// Filters, ordering and pagination for `tx history`. Values are taken as given on the command line.
+#[derive(Debug, Default)]
+pub struct HistoryQuery {
+    pub direction: Option<String>,
+    pub token: Option<String>,
+    pub counterparty: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub status: Option<String>,
+    pub min_amount: Option<String>,
+    pub sort: Option<String>,
+    pub desc: bool,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    /// Keeps the matching items, orders them and returns the requested page together with the number of matches.
+    /// `entry` gives the transaction of an item and the native token symbol of its network.
+    pub fn select<'a, T>(
+        &self,
+        items: Vec<T>,
+        account: Address,
+        entry: impl Fn(&T) -> (&StoredTransaction, &'a str),
+    ) -> Result<(Vec<T>, usize), Box<dyn Error>> {
+        let since = self.since.as_deref().map(HistoryService::parse_date).transpose()?;
+        let until = self
+            .until
+            .as_deref()
+            .map(HistoryService::parse_date)
+            .transpose()?
+            .map(|until| until + 86_400);
+        // The minimum as an integer scaled by its own decimal places, so rows of any decimals compare exactly.
+        let min_amount = match self.min_amount.as_deref() {
+            Some(min_amount) => {
+                let fraction_digits = min_amount.split_once('.').map_or(0, |(_, fraction)| fraction.trim_end_matches('0').len());
+                if fraction_digits > 77 {
+                    return Err(format!("Amount '{}' has too many decimal places", min_amount).into());
+                }
+                let min_amount = match min_amount.split_once('.') {
+                    Some((whole, fraction)) => format!("{}.{}", whole, fraction.trim_end_matches('0')),
+                    None => min_amount.to_string(),
+                };
+                let min_amount = min_amount.trim_end_matches('.');
+                Some((parse_units(min_amount, fraction_digits as u32)?, fraction_digits))
+            }
+            None => None,
+        };
+        let counterparty = match self.counterparty.as_deref() {
+            Some(counterparty) => {
+                Some(Address::from_str(counterparty).map_err(|_| format!("Invalid counterparty address '{}'", counterparty))?)
+            }
+            None => None,
+        };
+
+        let mut selected = Vec::new();
+        for item in items {
+            let (tx, native_token) = entry(&item);
+            if let Some(direction) = &self.direction {
+                if tx.direction(account) != direction {
+                    continue;
+                }
+            }
+            if let Some(status) = &self.status {
+                if tx.status.name() != status {
+                    continue;
+                }
+            }
+            if let Some(counterparty) = counterparty {
+                if tx.counterparty(account) != Some(counterparty) {
+                    continue;
+                }
+            }
+            if let Some(token) = &self.token {
+                let (symbol, contract, _) = tx.asset_amount(native_token);
+                let matches_contract = Address::from_str(token)
+                    .is_ok_and(|token| contract.as_deref().and_then(|c| Address::from_str(c).ok()) == Some(token));
+                if !symbol.eq_ignore_ascii_case(token) && !matches_contract {
+                    continue;
+                }
+            }
+            if since.is_some() || until.is_some() {
+                let Some(timestamp) = tx.timestamp else { continue };
+                if since.is_some_and(|since| timestamp < since) || until.is_some_and(|until| timestamp >= until) {
+                    continue;
+                }
+            }
+            if let Some((min_scaled, fraction_digits)) = min_amount {
+                // Token amounts without known decimals cannot be compared and count as below the minimum.
+                let comparable = tx.collection.is_some() || tx.token_contract.is_none() || tx.known_token_decimals().is_some();
+                let (amount, decimals) = tx.asset_base_amount();
+                if !comparable
+                    || amount.full_mul(U256::exp10(fraction_digits))
+                        < min_scaled.full_mul(U256::exp10(decimals as usize))
+                {
+                    continue;
+                }
+            }
+            selected.push(item);
+        }
+
+        match self.sort.as_deref() {
+            None | Some("date") => selected.sort_by_key(|item| entry(item).0.timestamp.unwrap_or_default()),
+            Some("amount") => selected.sort_by_key(|item| {
+                let (amount, decimals) = entry(item).0.asset_base_amount();
+                amount.saturating_mul(U256::exp10(18usize.saturating_sub(decimals as usize)))
+            }),
+            Some(sort) => return Err(format!("Unsupported sort key '{}' (expected date or amount)", sort).into()),
+        }
+        if self.desc {
+            selected.reverse();
+        }
+
+        let total = selected.len();
+        let page = selected
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok((page, total))
+    }
+}
+
+/// One exported history row. Every value is a string so CSV and JSON exports carry identical columns.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct HistoryRecord {
+    pub network: String,
+    pub chain_id: String,
+    pub timestamp: String,
+    pub hash: String,
+    pub status: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub direction: String,
+    pub counterparty: String,
+    pub asset: String,
+    pub asset_contract: String,
+    pub token_id: String,
+    pub amount: String,
+    pub fee_paid: String,
+    pub fee_asset: String,
+}
+
+impl HistoryRecord {
+    pub fn new(tx: &StoredTransaction, network: &NetworkInfo, network_name: &str, account: Address) -> Self {
+        let direction = tx.direction(account);
+        let (asset, asset_contract, amount) = tx.asset_amount(&network.native_token);
+        // Incoming transfers are paid for by the sender.
+        let fee_paid = match (direction, tx.fee_paid_as_u256()) {
+            ("in", _) => "0".to_string(),
+            (_, Some(fee)) => format_units(fee, 18).unwrap_or_else(|_| fee.to_string()),
+            (_, None) => String::new(),
+        };
+
+        HistoryRecord {
+            network: network_name.to_string(),
+            chain_id: tx.chain_id.unwrap_or(network.chain_id).to_string(),
+            timestamp: tx.timestamp.map(HistoryService::format_rfc3339).unwrap_or_default(),
+            hash: tx.hash.clone().unwrap_or_default(),
+            status: tx.status.name().to_string(),
+            kind: tx.kind.clone().unwrap_or_default(),
+            direction: direction.to_string(),
+            counterparty: tx.counterparty(account).as_ref().map(checksum).unwrap_or_default(),
+            asset,
+            asset_contract: asset_contract.unwrap_or_default(),
+            token_id: tx.token_id.clone().unwrap_or_default(),
+            amount,
+            fee_paid,
+            fee_asset: network.native_token.clone(),
+        }
+    }
+
+    fn values(&self) -> [&str; 14] {
+        [
+            &self.network,
+            &self.chain_id,
+            &self.timestamp,
+            &self.hash,
+            &self.status,
+            &self.kind,
+            &self.direction,
+            &self.counterparty,
+            &self.asset,
+            &self.asset_contract,
+            &self.token_id,
+            &self.amount,
+            &self.fee_paid,
+            &self.fee_asset,
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct HistoryFile {
+    version: u32,
+    transactions: Vec<StoredTransaction>,
+}
+
+pub struct HistoryService;
+
+impl HistoryService {
+    /// Returns the transactions and whether the file used an older schema and was upgraded.
+    pub fn parse(data: &str, chain_id: Option<u64>) -> Result<(Vec<StoredTransaction>, bool), Box<dyn Error>> {
+        let value: serde_json::Value = serde_json::from_str(data)?;
+        if value.is_array() {
+            let mut transactions: Vec<StoredTransaction> = serde_json::from_value(value)?;
+            for tx in transactions.iter_mut() {
+                Self::upgrade(tx, chain_id);
+            }
+            return Ok((transactions, true));
+        }
+
+        let file: HistoryFile = serde_json::from_value(value)?;
+        if file.version > HISTORY_VERSION {
+            return Err(format!(
+                "Unsupported history version {} (expected at most {})",
+                file.version, HISTORY_VERSION
+            )
+                .into());
+        }
+        Ok((file.transactions, false))
+    }
+
+    pub fn load(path: &Path, chain_id: Option<u64>) -> Result<(Vec<StoredTransaction>, bool), Box<dyn Error>> {
+        if !path.exists() {
+            return Ok((Vec::new(), false));
+        }
+        Self::parse(&fs::read_to_string(path)?, chain_id)
+    }
+
+    pub fn save(path: &Path, transactions: &[StoredTransaction]) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = HistoryFile {
+            version: HISTORY_VERSION,
+            transactions: transactions.to_vec(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+
+    /// Version 1 entries were recorded for plain and ERC-20 transfers only, without hash or status.
+    fn upgrade(tx: &mut StoredTransaction, chain_id: Option<u64>) {
+        if tx.kind.is_none() {
+            let kind = if tx.token_value.is_some() { "token_transfer" } else { "transfer" };
+            tx.kind = Some(kind.to_string());
+        }
+        if tx.chain_id.is_none() {
+            tx.chain_id = chain_id;
+        }
+        if tx.status == TxStatus::Unknown && tx.hash.is_some() {
+            tx.status = TxStatus::Pending;
+        }
+    }
+
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default()
+    }
+
+    /// Formats unix seconds as `YYYY-MM-DD HH:MM:SS` in UTC.
+    pub fn format_timestamp(timestamp: u64) -> String {
+        let days = (timestamp / 86_400) as i64;
+        let seconds = timestamp % 86_400;
+        let (year, month, day) = Self::civil_from_days(days);
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    }
+
+    /// Parses a `YYYY-MM-DD` date as unix seconds at midnight UTC.
+    pub fn parse_date(date: &str) -> Result<u64, String> {
+        let invalid = || format!("Invalid date '{}' (expected YYYY-MM-DD)", date);
+        let parts: Vec<&str> = date.split('-').collect();
+        let [year, month, day] = parts.as_slice() else { return Err(invalid()) };
+        let year: i64 = year.parse().map_err(|_| invalid())?;
+        let month: u32 = month.parse().map_err(|_| invalid())?;
+        let day: u32 = day.parse().map_err(|_| invalid())?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return Err(invalid());
+        }
+        let days = Self::days_from_civil(year, month, day);
+        if days < 0 || Self::civil_from_days(days) != (year, month, day) {
+            return Err(invalid());
+        }
+        Ok(days as u64 * 86_400)
+    }
+
+    /// Formats unix seconds as an RFC 3339 UTC timestamp, e.g. `2023-11-14T22:13:20Z`.
+    pub fn format_rfc3339(timestamp: u64) -> String {
+        format!("{}Z", Self::format_timestamp(timestamp).replace(' ', "T"))
+    }
+
+    /// Keeps the fee of a transaction on one row only: a hash can produce several rows (sync imports every
+    /// Transfer log of a transaction), and the fee was paid once.
+    pub fn attribute_fees(records: &mut [HistoryRecord]) {
+        let mut charged = HashSet::new();
+        for record in records.iter_mut() {
+            if record.hash.is_empty() || record.fee_paid.is_empty() || record.fee_paid == "0" {
+                continue;
+            }
+            if !charged.insert((record.network.clone(), record.hash.to_lowercase())) {
+                record.fee_paid = "0".to_string();
+            }
+        }
+    }
+
+    pub fn to_csv(records: &[HistoryRecord]) -> String {
+        let mut csv = EXPORT_COLUMNS.join(",");
+        csv.push('\n');
+        for record in records {
+            let row: Vec<String> = record.values().iter().map(|value| Self::csv_field(value)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    fn csv_field(value: &str) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    // Howard Hinnant's civil-to-days algorithm for the proleptic Gregorian calendar.
+    fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = month as i64;
+        let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    // Howard Hinnant's days-to-civil algorithm for the proleptic Gregorian calendar.
+    fn civil_from_days(days: i64) -> (i64, u32, u32) {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        (year, month, day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrades_version_one_history() {
+        let data = r#"[
+            {"type":"Legacy","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x5208","gas_price":"0x1","value":"0x0","token_value":"100"},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x5208","gas_price":"0x1","value":"0x10","token_value":null,"hash":"0x0000000000000000000000000000000000000000000000000000000000000001","nonce":"0x3","kind":"transfer"}
+        ]"#;
+        let (transactions, upgraded) = HistoryService::parse(data, Some(1)).unwrap();
+
+        assert!(upgraded);
+        assert_eq!(transactions[0].kind.as_deref(), Some("token_transfer"));
+        assert_eq!(transactions[0].chain_id, Some(1));
+        assert_eq!(transactions[0].status, TxStatus::Unknown);
+        assert_eq!(transactions[1].status, TxStatus::Pending);
+        assert_eq!(transactions[1].nonce_as_u256(), Some(U256::from(3)));
+    }
+
+    #[test]
+    fn test_parses_current_history() {
+        let data = r#"{"version":2,"transactions":[
+            {"type":"Legacy","from":"0x0000000000000000000000000000000000000001","to":null,"gas":"0x1","gas_price":"0x1","value":"0x0","token_value":null,"status":"success","timestamp":1700000000}
+        ]}"#;
+        let (transactions, upgraded) = HistoryService::parse(data, None).unwrap();
+
+        assert!(!upgraded);
+        assert_eq!(transactions[0].status, TxStatus::Success);
+        assert_eq!(transactions[0].timestamp, Some(1_700_000_000));
+        assert!(HistoryService::parse(r#"{"version":3,"transactions":[]}"#, None).is_err());
+    }
+
+    #[test]
+    fn test_apply_receipt() {
+        let data = r#"{"version":2,"transactions":[
+            {"type":"Legacy","from":"0x0000000000000000000000000000000000000001","to":null,"gas":"0x1","gas_price":"0x1","value":"0x0","token_value":null,"kind":"deploy","status":"pending"}
+        ]}"#;
+        let (mut transactions, _) = HistoryService::parse(data, None).unwrap();
+        let receipt = TransactionReceipt {
+            status: Some(U64::one()),
+            gas_used: Some(U256::from(100_000)),
+            effective_gas_price: Some(U256::from(2)),
+            contract_address: Some(Address::from_low_u64_be(0x70)),
+            ..Default::default()
+        };
+        transactions[0].apply_receipt(&receipt);
+
+        assert_eq!(transactions[0].status, TxStatus::Success);
+        assert_eq!(transactions[0].fee_paid.as_deref(), Some("200000"));
+        assert_eq!(transactions[0].contract_address.as_deref(), Some("0x0000000000000000000000000000000000000070"));
+    }
+
+    #[test]
+    fn test_export_record() {
+        let account = Address::from_low_u64_be(1);
+        let network = NetworkInfo {
+            name: "ethereum".to_string(),
+            url: None,
+            native_token: "ETH".to_string(),
+            chain_id: 1,
+            ens_registry: None,
+        };
+        let data = r#"{"version":2,"transactions":[
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000002","to":"0x0000000000000000000000000000000000000001","gas":"0x0","gas_price":"0x0","value":"0x0","token_value":"1500000","token_contract":"0x0000000000000000000000000000000000000070","token_symbol":"USD, Coin","token_decimals":6,"hash":"0x01","status":"success","timestamp":1700000000},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000003","gas":"0x5208","gas_price":"0x1","value":"0xde0b6b3a7640000","token_value":null,"status":"success","fee_paid":"21000000000000"}
+        ]}"#;
+        let (transactions, _) = HistoryService::parse(data, None).unwrap();
+        let records: Vec<HistoryRecord> = transactions
+            .iter()
+            .map(|tx| HistoryRecord::new(tx, &network, "ethereum_mainnet", account))
+            .collect();
+
+        assert_eq!(records[0].direction, "in");
+        assert_eq!(records[0].amount, "1.5");
+        assert_eq!(records[0].fee_paid, "0");
+        assert_eq!(records[0].timestamp, "2023-11-14T22:13:20Z");
+        assert_eq!(records[1].direction, "out");
+        assert_eq!(records[1].counterparty, "0x0000000000000000000000000000000000000003");
+        assert_eq!(records[1].amount, "1");
+        assert_eq!(records[1].fee_paid, "0.000021");
+
+        let csv = HistoryService::to_csv(&records);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], EXPORT_COLUMNS.join(","));
+        assert!(lines[1].contains(",\"USD, Coin\","));
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_fee_attributed_once_per_hash() {
+        let account = Address::from_low_u64_be(1);
+        let network = NetworkInfo {
+            name: "ethereum".to_string(),
+            url: None,
+            native_token: "ETH".to_string(),
+            chain_id: 1,
+            ens_registry: None,
+        };
+        // A swap imported by sync: tokens in and out of the account, all from one transaction.
+        let data = r#"{"version":2,"transactions":[
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000002","to":"0x0000000000000000000000000000000000000001","gas":"0x0","gas_price":"0x0","value":"0x0","token_value":"5","token_contract":"0x0000000000000000000000000000000000000070","hash":"0x0a","status":"success","fee_paid":"21000"},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x0","gas_price":"0x0","value":"0x0","token_value":"7","token_contract":"0x0000000000000000000000000000000000000071","hash":"0x0A","status":"success","fee_paid":"21000"},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000003","gas":"0x0","gas_price":"0x0","value":"0x0","token_value":"9","token_contract":"0x0000000000000000000000000000000000000072","hash":"0x0a","status":"success","fee_paid":"21000"},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000003","gas":"0x0","gas_price":"0x0","value":"0x1","token_value":null,"hash":"0x0b","status":"success","fee_paid":"21000"}
+        ]}"#;
+        let (transactions, _) = HistoryService::parse(data, None).unwrap();
+        let mut records: Vec<HistoryRecord> = transactions
+            .iter()
+            .map(|tx| HistoryRecord::new(tx, &network, "ethereum_mainnet", account))
+            .collect();
+        HistoryService::attribute_fees(&mut records);
+
+        let fees: Vec<&str> = records.iter().map(|record| record.fee_paid.as_str()).collect();
+        assert_eq!(fees, vec!["0", "0.000000000000021", "0", "0.000000000000021"]);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(HistoryService::parse_date("1970-01-01"), Ok(0));
+        assert_eq!(HistoryService::parse_date("2000-02-29"), Ok(951_782_400));
+        assert!(HistoryService::parse_date("2023-02-29").is_err());
+        assert!(HistoryService::parse_date("2023-13-01").is_err());
+        assert!(HistoryService::parse_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_history_query() {
+        let account = Address::from_low_u64_be(1);
+        let data = r#"{"version":2,"transactions":[
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x0","gas_price":"0x0","value":"0x1bc16d674ec80000","token_value":null,"status":"success","timestamp":1700000000},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000003","to":"0x0000000000000000000000000000000000000001","gas":"0x0","gas_price":"0x0","value":"0x0","token_value":"5000000","token_contract":"0x0000000000000000000000000000000000000070","token_symbol":"USDC","token_decimals":6,"status":"success","timestamp":1700100000},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000003","gas":"0x0","gas_price":"0x0","value":"0xde0b6b3a7640000","token_value":null,"status":"pending","timestamp":1700200000}
+        ]}"#;
+        let (transactions, _) = HistoryService::parse(data, None).unwrap();
+        let select = |query: HistoryQuery| {
+            let (page, total) = query.select(transactions.clone(), account, |tx| (tx, "ETH")).unwrap();
+            (page.iter().map(|tx| tx.timestamp.unwrap()).collect::<Vec<u64>>(), total)
+        };
+
+        let outgoing = HistoryQuery { direction: Some("out".to_string()), ..Default::default() };
+        assert_eq!(select(outgoing), (vec![1_700_000_000, 1_700_200_000], 2));
+
+        let usdc = HistoryQuery { token: Some("usdc".to_string()), ..Default::default() };
+        assert_eq!(select(usdc).1, 1);
+
+        let counterparty = HistoryQuery { counterparty: Some(format!("{:?}", Address::from_low_u64_be(3))), ..Default::default() };
+        assert_eq!(select(counterparty).1, 2);
+
+        let dated = HistoryQuery { since: Some("2023-11-15".to_string()), until: Some("2023-11-16".to_string()), ..Default::default() };
+        assert_eq!(select(dated).0, vec![1_700_100_000]);
+
+        let large = HistoryQuery { min_amount: Some("1.5".to_string()), ..Default::default() };
+        assert_eq!(select(large).0, vec![1_700_000_000, 1_700_100_000]);
+
+        let by_amount = HistoryQuery { sort: Some("amount".to_string()), desc: true, limit: Some(1), offset: 1, ..Default::default() };
+        assert_eq!(select(by_amount), (vec![1_700_000_000], 3));
+
+        let pending = HistoryQuery { status: Some("pending".to_string()), ..Default::default() };
+        assert_eq!(select(pending).0, vec![1_700_200_000]);
+    }
+
+    #[test]
+    fn test_min_amount_across_decimals() {
+        let account = Address::from_low_u64_be(1);
+        let data = r#"{"version":2,"transactions":[
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x0","gas_price":"0x0","value":"0x0","token_value":"1","token_id":"7","collection":"0x0000000000000000000000000000000000000080","kind":"nft_transfer","status":"success","timestamp":1},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x0","gas_price":"0x0","value":"0x0","token_value":"5000000","token_contract":"0x0000000000000000000000000000000000000070","status":"success","timestamp":2},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x0","gas_price":"0x0","value":"0x0","token_value":"500000","token_contract":"0x0000000000000000000000000000000000000071","token_decimals":6,"status":"success","timestamp":3},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x0","gas_price":"0x0","value":"0x8ac7230489e80000","token_value":null,"status":"success","timestamp":4}
+        ]}"#;
+        let (transactions, _) = HistoryService::parse(data, None).unwrap();
+        let select = |min_amount: &str| {
+            let query = HistoryQuery { min_amount: Some(min_amount.to_string()), ..Default::default() };
+            let (page, _) = query.select(transactions.clone(), account, |tx| (tx, "ETH")).unwrap();
+            page.iter().map(|tx| tx.timestamp.unwrap()).collect::<Vec<u64>>()
+        };
+
+        assert_eq!(select("0.5"), vec![1, 3, 4]);
+        assert_eq!(select("0.50"), vec![1, 3, 4]);
+        assert_eq!(select("0.5000001"), vec![1, 4]);
+        assert_eq!(select("10"), vec![4]);
+        assert_eq!(select("10.000000000000000001"), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(HistoryService::format_timestamp(0), "1970-01-01 00:00:00");
+        assert_eq!(HistoryService::format_timestamp(1_700_000_000), "2023-11-14 22:13:20");
+        assert_eq!(HistoryService::format_timestamp(951_782_400), "2000-02-29 00:00:00");
+    }
+}
@@ -1,4 +1,21 @@
 pub mod account;
+pub mod address;
+pub mod address_book;
+pub mod batch;
+pub mod confirmation;
+pub mod contract;
 pub mod crypto;
+pub mod ens;
+pub mod fees;
+pub mod history;
 pub mod network;
+pub mod nft;
+pub mod nonce;
+pub mod offline;
+pub mod policy;
+pub mod selectors;
+pub mod signing;
+pub mod sync;
+pub mod tokens;
 pub mod transaction;
+pub mod units;
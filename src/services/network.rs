@@ -2,7 +2,8 @@ use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Write};
 use serde_json::{json, Value};
-use crate::config::{STORAGE_FILE};
+use crate::config::{ENS_REGISTRY, STORAGE_FILE};
+use crate::services::address::{checksum, parse_address};
 
 #[derive(Debug, Clone)]
 pub struct NetworkInfo {
@@ -10,6 +11,8 @@ pub struct NetworkInfo {
     pub url: Option<String>,
     pub native_token: String,
     pub chain_id: u64,
+    /// ENS registry used to resolve names; none on networks without ENS.
+    pub ens_registry: Option<String>,
 }
 
 pub struct NetworkService {
@@ -28,6 +31,7 @@ impl NetworkService {
                 url: None,
                 native_token: "ETH".to_string(),
                 chain_id: 1,
+                ens_registry: Some(ENS_REGISTRY.to_string()),
             },
         );
 
@@ -38,6 +42,7 @@ impl NetworkService {
                 url: None,
                 native_token: "ETH".to_string(),
                 chain_id: 11155111,
+                ens_registry: Some(ENS_REGISTRY.to_string()),
             },
         );
 
@@ -48,6 +53,7 @@ impl NetworkService {
                 url: None,
                 native_token: "POL".to_string(),
                 chain_id: 137,
+                ens_registry: None,
             },
         );
 
@@ -58,6 +64,7 @@ impl NetworkService {
                 url: None,
                 native_token: "POL".to_string(),
                 chain_id: 80002,
+                ens_registry: None,
             },
         );
 
@@ -68,6 +75,7 @@ impl NetworkService {
                 url: None,
                 native_token: "ETH".to_string(),
                 chain_id: 10,
+                ens_registry: None,
             },
         );
 
@@ -78,6 +86,7 @@ impl NetworkService {
                 url: None,
                 native_token: "ETH".to_string(),
                 chain_id: 11155420,
+                ens_registry: None,
             },
         );
 
@@ -88,6 +97,7 @@ impl NetworkService {
                 url: None,
                 native_token: "BNB".to_string(),
                 chain_id: 56,
+                ens_registry: None,
             },
         );
 
@@ -98,6 +108,7 @@ impl NetworkService {
                 url: None,
                 native_token: "BNB".to_string(),
                 chain_id: 97,
+                ens_registry: None,
             },
         );
 
@@ -108,6 +119,7 @@ impl NetworkService {
                 url: None,
                 native_token: "ETH".to_string(),
                 chain_id: 42161,
+                ens_registry: None,
             },
         );
 
@@ -118,6 +130,7 @@ impl NetworkService {
                 url: None,
                 native_token: "ETH".to_string(),
                 chain_id: 421614,
+                ens_registry: None,
             },
         );
 
@@ -140,6 +153,26 @@ impl NetworkService {
         }
     }
 
+    pub fn set_ens_registry(&mut self, network_name: &str, registry: Option<&str>) {
+        let registry = match registry.map(|registry| parse_address(registry, "registry")).transpose() {
+            Ok(registry) => registry,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        if let Some(network) = self.networks.get_mut(network_name) {
+            network.ens_registry = registry.as_ref().map(checksum);
+            match registry {
+                Some(registry) => println!("ENS registry for '{}' has been set to {}.", network_name, checksum(&registry)),
+                None => println!("ENS resolution has been disabled for '{}'.", network_name),
+            }
+            self.save_state();
+        } else {
+            println!("Network '{}' not found.", network_name);
+        }
+    }
+
     pub fn get_network(&self, network_name: &str) -> Option<&NetworkInfo> {
         self.networks.get(network_name)
     }
@@ -198,6 +231,7 @@ impl NetworkService {
                     url: Some(url.to_string()),
                     native_token: native_token.to_string(),
                     chain_id,
+                    ens_registry: None,
                 },
             );
             println!("Network '{}' added successfully.", network_name);
@@ -214,6 +248,7 @@ impl NetworkService {
                     "url": value.url,
                     "native_token": value.native_token,
                     "chain_id": value.chain_id,
+                    "ens_registry": value.ens_registry,
                 }))
             }).collect::<HashMap<_, _>>()
         });
@@ -250,6 +285,11 @@ impl NetworkService {
                     url: value["url"].as_str().map(|s| s.to_string()),
                     native_token: value["native_token"].as_str().unwrap_or_default().to_string(),
                     chain_id: value["chain_id"].as_u64().unwrap_or_default(),
+                    // State saved before ENS support keeps the built-in registry.
+                    ens_registry: match value.get("ens_registry") {
+                        Some(registry) => registry.as_str().map(|s| s.to_string()),
+                        None => self.networks.get(key).and_then(|network| network.ens_registry.clone()),
+                    },
                 };
                 self.networks.insert(key.clone(), network_info);
             }
@@ -278,6 +318,7 @@ impl NetworkService {
                 println!("Chain ID: {}", network.chain_id);
                 println!("Native token: {}", network.native_token);
                 println!("RPC URL: {:?}", network.url.as_deref().unwrap_or(&"None".to_string()));
+                println!("ENS registry: {}", network.ens_registry.as_deref().unwrap_or("None"));
             } else {
                 println!("Network '{}' not found.", current_network_name);
             }
@@ -303,4 +344,32 @@ impl NetworkService {
     pub fn get_network_name(&self) -> Option<String> {
         self.current_network.clone()
     }
+
+    pub fn ensure_current_chain_id(&self, chain_id: u64) -> Result<(), String> {
+        let network_name = self.current_network.as_deref().ok_or("No network is currently selected.")?;
+        let network = self.get_network(network_name).ok_or(format!("Network '{}' not found.", network_name))?;
+        if network.chain_id != chain_id {
+            return Err(format!(
+                "Chain ID mismatch: transaction is for chain {}, but network '{}' has chain ID {}.",
+                chain_id, network_name, network.chain_id
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn ensure_network_chain_id(&self, network_name: &str, chain_id: u64) -> Result<(), String> {
+        match self.get_network(network_name) {
+            Some(network) if network.chain_id != chain_id => Err(format!(
+                "Chain ID mismatch: transaction is for chain {}, but network '{}' has chain ID {}.",
+                chain_id, network_name, network.chain_id
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get_current_network(&self) -> Option<&NetworkInfo> {
+        self.current_network
+            .as_deref()
+            .and_then(|network_name| self.get_network(network_name))
+    }
 }
@@ -0,0 +1,99 @@
+/*
+    NftService - helpers for ERC-721 and ERC-1155 collections.
+    - Standards are detected through ERC-165 `supportsInterface`.
+    - `safeTransferFrom` calldata is decoded to record token ID and collection in history.
+*/
+use ethers::core::types::{Address, U256};
+
+pub const ERC721_INTERFACE_ID: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+pub const ERC1155_INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+const ERC721_SAFE_TRANSFER_SELECTOR: [u8; 4] = [0x42, 0x84, 0x2e, 0x0e];
+const ERC1155_SAFE_TRANSFER_SELECTOR: [u8; 4] = [0xf2, 0x42, 0x43, 0x2a];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NftStandard {
+    Erc721,
+    Erc1155,
+}
+
+impl NftStandard {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NftStandard::Erc721 => "ERC-721",
+            NftStandard::Erc1155 => "ERC-1155",
+        }
+    }
+}
+
+pub struct NftTransfer {
+    pub to: Address,
+    pub token_id: U256,
+    pub amount: U256,
+}
+
+pub struct NftService;
+
+impl NftService {
+    /// ERC-1155 metadata URIs may contain `{id}`, replaced by the lowercase 64-digit hex token ID.
+    pub fn expand_uri(uri: &str, token_id: U256) -> String {
+        uri.replace("{id}", &format!("{:064x}", token_id))
+    }
+
+    pub fn decode_transfer(data: &[u8]) -> Option<NftTransfer> {
+        let selector = data.get(..4)?;
+        let word = |index: usize| data.get(4 + index * 32..4 + (index + 1) * 32);
+
+        if selector == ERC721_SAFE_TRANSFER_SELECTOR {
+            Some(NftTransfer {
+                to: Address::from_slice(&word(1)?[12..]),
+                token_id: U256::from_big_endian(word(2)?),
+                amount: U256::one(),
+            })
+        } else if selector == ERC1155_SAFE_TRANSFER_SELECTOR {
+            Some(NftTransfer {
+                to: Address::from_slice(&word(1)?[12..]),
+                token_id: U256::from_big_endian(word(2)?),
+                amount: U256::from_big_endian(word(3)?),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{AbiParser, Token};
+
+    #[test]
+    fn test_expand_uri() {
+        assert_eq!(
+            NftService::expand_uri("https://example.com/{id}.json", U256::from(314592)),
+            "https://example.com/000000000000000000000000000000000000000000000000000000000004cce0.json"
+        );
+    }
+
+    #[test]
+    fn test_decode_erc1155_transfer() {
+        let function = AbiParser::default()
+            .parse_function("safeTransferFrom(address,address,uint256,uint256,bytes)")
+            .unwrap();
+        let to = Address::from_low_u64_be(0xdead);
+        let data = function
+            .encode_input(&[
+                Token::Address(Address::from_low_u64_be(1)),
+                Token::Address(to),
+                Token::Uint(U256::from(7)),
+                Token::Uint(U256::from(3)),
+                Token::Bytes(vec![]),
+            ])
+            .unwrap();
+
+        let transfer = NftService::decode_transfer(&data).unwrap();
+        assert_eq!(transfer.to, to);
+        assert_eq!(transfer.token_id, U256::from(7));
+        assert_eq!(transfer.amount, U256::from(3));
+    }
+}
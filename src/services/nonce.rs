@@ -0,0 +1,65 @@
+/*
+    NonceManager - picks nonces and replacement fees for outgoing transactions.
+    - Next nonce accounts for transactions recorded in history that are not yet mined but still known to the node;
+      nonces of dropped transactions are reused.
+    - Replacement fees satisfy the node price bump rule (at least +10%) for speed-up and cancel.
+*/
+use ethers::core::types::U256;
+
+pub struct NonceManager;
+
+impl NonceManager {
+    /// `confirmed` is the mined transaction count, `pending` the count including the node's mempool,
+    /// and `known` the nonces of transactions from local history that the node still knows about.
+    pub fn next_nonce(confirmed: U256, pending: U256, known: &[U256]) -> U256 {
+        let mut next = confirmed.max(pending);
+        while known.contains(&next) {
+            next += U256::one();
+        }
+        next
+    }
+
+    pub fn bump_fee(previous: U256) -> U256 {
+        (previous * 11 + 9) / 10
+    }
+
+    pub fn replacement_fee(previous: U256, current: U256) -> U256 {
+        Self::bump_fee(previous).max(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_nonce_skips_recorded_pending() {
+        let recorded = vec![U256::from(3), U256::from(4), U256::from(5)];
+        assert_eq!(NonceManager::next_nonce(U256::from(4), U256::from(4), &recorded), U256::from(6));
+    }
+
+    #[test]
+    fn test_next_nonce_prefers_node_pending_count() {
+        let recorded = vec![U256::from(1)];
+        assert_eq!(NonceManager::next_nonce(U256::from(2), U256::from(5), &recorded), U256::from(5));
+    }
+
+    #[test]
+    fn test_next_nonce_ignores_gaps() {
+        let recorded = vec![U256::from(7)];
+        assert_eq!(NonceManager::next_nonce(U256::from(5), U256::from(5), &recorded), U256::from(5));
+    }
+
+    #[test]
+    fn test_next_nonce_skips_known_above_pending() {
+        let known = vec![U256::from(5), U256::from(6)];
+        assert_eq!(NonceManager::next_nonce(U256::from(3), U256::from(5), &known), U256::from(7));
+    }
+
+    #[test]
+    fn test_replacement_fee() {
+        assert_eq!(NonceManager::bump_fee(U256::from(100)), U256::from(110));
+        assert_eq!(NonceManager::bump_fee(U256::from(101)), U256::from(112));
+        assert_eq!(NonceManager::replacement_fee(U256::from(100), U256::from(150)), U256::from(150));
+    }
+}
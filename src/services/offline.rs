@@ -0,0 +1,444 @@
+/*
+    OfflineService - building, signing and decoding transactions without network access.
+    - Every field (nonce, chain ID, fees, gas) must be given explicitly.
+    - Signed transactions are exchanged as 0x-prefixed raw RLP hex.
+    - Air-gapped workflows exchange unsigned and signed transaction files (see README for the format).
+*/
+use std::error::Error;
+use std::fs;
+use std::str::FromStr;
+use ethers::core::types::{Address, Bytes, Eip1559TransactionRequest, Signature, TransactionRequest, U256};
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::signers::LocalWallet;
+use ethers::utils::rlp::Rlp;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::services::address::{checksum, parse_address, recipient_warnings};
+use crate::services::confirmation::{AssetInfo, TxSummary};
+use crate::services::contract::{ContractService, TokenCall};
+
+pub const TX_FILE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TxFileNetwork {
+    pub name: String,
+    pub chain_id: u64,
+    pub native_token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnsignedTxFields {
+    pub from: String,
+    pub to: String,
+    pub value: String,
+    pub data: String,
+    pub nonce: u64,
+    pub gas_limit: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TxFileAsset {
+    pub symbol: String,
+    pub decimals: u32,
+    pub contract: Option<String>,
+    pub recipient: String,
+    pub amount: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnsignedTxFile {
+    pub version: u32,
+    pub network: TxFileNetwork,
+    pub transaction: UnsignedTxFields,
+    pub asset: TxFileAsset,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SignedTxFile {
+    pub version: u32,
+    pub network: TxFileNetwork,
+    pub hash: String,
+    pub raw_transaction: String,
+}
+
+impl UnsignedTxFile {
+    pub fn from_transaction(tx: &TypedTransaction, summary: &TxSummary) -> Result<Self, Box<dyn Error>> {
+        let from = tx.from().ok_or("Transaction has no sender")?;
+        let to = tx.to_addr().ok_or("Transaction has no recipient")?;
+        let nonce = tx.nonce().ok_or("Transaction has no nonce")?;
+        let gas_limit = tx.gas().ok_or("Transaction has no gas limit")?;
+
+        let (gas_price, max_fee_per_gas, max_priority_fee_per_gas) = match tx {
+            TypedTransaction::Eip1559(inner) => (
+                None,
+                inner.max_fee_per_gas.map(|fee| fee.to_string()),
+                inner.max_priority_fee_per_gas.map(|fee| fee.to_string()),
+            ),
+            _ => (tx.gas_price().map(|price| price.to_string()), None, None),
+        };
+
+        Ok(UnsignedTxFile {
+            version: TX_FILE_VERSION,
+            network: TxFileNetwork {
+                name: summary.network_name.clone(),
+                chain_id: summary.chain_id,
+                native_token: summary.native_token.clone(),
+            },
+            transaction: UnsignedTxFields {
+                from: checksum(from),
+                to: checksum(to),
+                value: tx.value().copied().unwrap_or_default().to_string(),
+                data: format!("{}", tx.data().cloned().unwrap_or_default()),
+                nonce: nonce.as_u64(),
+                gas_limit: gas_limit.to_string(),
+                gas_price,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            },
+            asset: TxFileAsset {
+                symbol: summary.asset.symbol.clone(),
+                decimals: summary.asset.decimals,
+                contract: summary.asset.contract.as_ref().map(checksum),
+                recipient: summary.to.as_ref().map(checksum).unwrap_or_default(),
+                amount: summary.amount.to_string(),
+            },
+        })
+    }
+
+    pub fn params(&self) -> OfflineTxParams<'_> {
+        OfflineTxParams {
+            to: &self.transaction.to,
+            value: &self.transaction.value,
+            nonce: self.transaction.nonce,
+            chain_id: self.network.chain_id,
+            gas_limit: &self.transaction.gas_limit,
+            gas_price: self.transaction.gas_price.as_deref(),
+            max_fee: self.transaction.max_fee_per_gas.as_deref(),
+            max_priority_fee: self.transaction.max_priority_fee_per_gas.as_deref(),
+            data: Some(&self.transaction.data),
+        }
+    }
+
+    /// Review summary of the transaction that will actually be signed, built from `tx` (the `transaction` block).
+    /// The `asset` block only supplies the token symbol and decimals, and the file is rejected when its recipient,
+    /// contract or amount disagree with the transaction.
+    pub fn summary(&self, tx: &TypedTransaction) -> Result<TxSummary, Box<dyn Error>> {
+        let gas_limit = *tx.gas().ok_or("Transaction has no gas limit")?;
+        let fee_per_gas = match tx {
+            TypedTransaction::Eip1559(inner) => inner.max_fee_per_gas,
+            _ => tx.gas_price(),
+        }
+        .ok_or("Transaction has no fee")?;
+        let from = Address::from_str(&self.transaction.from).map_err(|_| "Invalid sender address format")?;
+        let target = *tx.to_addr().ok_or("Transaction has no recipient")?;
+        let value = tx.value().copied().unwrap_or_default();
+        let data = tx.data().cloned().unwrap_or_default();
+
+        let (to, amount, asset) = if data.is_empty() {
+            let asset = AssetInfo {
+                symbol: self.network.native_token.clone(),
+                decimals: 18,
+                contract: None,
+            };
+            (target, value, asset)
+        } else {
+            match ContractService::decode_token_call(&data) {
+                Some(TokenCall::Transfer { to, amount }) if value.is_zero() => {
+                    let asset = AssetInfo {
+                        symbol: self.asset.symbol.clone(),
+                        decimals: self.asset.decimals,
+                        contract: Some(target),
+                    };
+                    (to, amount, asset)
+                }
+                _ => return Err("Transaction file is not a native or ERC-20 transfer; refusing to sign".into()),
+            }
+        };
+
+        let claimed_contract = match &self.asset.contract {
+            Some(contract) => Some(Address::from_str(contract).map_err(|_| "Invalid token address format")?),
+            None => None,
+        };
+        let claimed_to = Address::from_str(&self.asset.recipient).map_err(|_| "Invalid destination address format")?;
+        let claimed_amount = U256::from_dec_str(&self.asset.amount).map_err(|_| "Invalid amount format")?;
+        if claimed_contract != asset.contract || claimed_to != to || claimed_amount != amount {
+            return Err("Transaction file is inconsistent: the asset block does not match the transaction to be signed".into());
+        }
+
+        let mut warnings = recipient_warnings(to, from, asset.contract);
+        if asset.contract.is_some() {
+            warnings.push("Token symbol and decimals come from the file and cannot be verified offline".to_string());
+        }
+
+        Ok(TxSummary {
+            network_name: self.network.name.clone(),
+            chain_id: self.network.chain_id,
+            native_token: self.network.native_token.clone(),
+            from,
+            to: Some(to),
+            to_name: None,
+            to_kind: None,
+            amount,
+            asset,
+            call: None,
+            gas_limit,
+            fee_low: gas_limit * fee_per_gas,
+            fee_high: gas_limit * fee_per_gas,
+            warnings,
+        })
+    }
+}
+
+pub struct OfflineTxParams<'a> {
+    pub to: &'a str,
+    pub value: &'a str,
+    pub nonce: u64,
+    pub chain_id: u64,
+    pub gas_limit: &'a str,
+    pub gas_price: Option<&'a str>,
+    pub max_fee: Option<&'a str>,
+    pub max_priority_fee: Option<&'a str>,
+    pub data: Option<&'a str>,
+}
+
+pub struct OfflineService;
+
+impl OfflineService {
+    pub fn build_transaction(params: &OfflineTxParams, from: Address) -> Result<TypedTransaction, Box<dyn Error>> {
+        let to = parse_address(params.to, "destination")?;
+        let value = U256::from_dec_str(params.value).map_err(|_| "Invalid amount format")?;
+        let gas_limit = U256::from_dec_str(params.gas_limit).map_err(|_| "Invalid gas limit format")?;
+        let data = match params.data {
+            Some(data) => Bytes::from_str(data).map_err(|_| "Invalid data format")?,
+            None => Bytes::default(),
+        };
+
+        let mut tx: TypedTransaction = match (params.gas_price, params.max_fee, params.max_priority_fee) {
+            (Some(gas_price), None, None) => {
+                let gas_price = U256::from_dec_str(gas_price).map_err(|_| "Invalid gas price format")?;
+                TransactionRequest::new()
+                    .to(to)
+                    .value(value)
+                    .data(data)
+                    .gas_price(gas_price)
+                    .into()
+            }
+            (None, Some(max_fee), Some(max_priority_fee)) => {
+                let max_fee = U256::from_dec_str(max_fee).map_err(|_| "Invalid max fee format")?;
+                let max_priority_fee = U256::from_dec_str(max_priority_fee).map_err(|_| "Invalid max priority fee format")?;
+                if max_priority_fee > max_fee {
+                    return Err("Max priority fee cannot exceed max fee".into());
+                }
+                Eip1559TransactionRequest::new()
+                    .to(to)
+                    .value(value)
+                    .data(data)
+                    .max_fee_per_gas(max_fee)
+                    .max_priority_fee_per_gas(max_priority_fee)
+                    .into()
+            }
+            _ => return Err("Specify either --gas-price, or both --max-fee and --max-priority-fee".into()),
+        };
+
+        tx.set_from(from);
+        tx.set_nonce(params.nonce);
+        tx.set_chain_id(params.chain_id);
+        tx.set_gas(gas_limit);
+        Ok(tx)
+    }
+
+    pub fn sign(tx: &TypedTransaction, wallet: &LocalWallet) -> Result<Bytes, Box<dyn Error>> {
+        let signature = wallet.sign_transaction_sync(tx)?;
+        Ok(tx.rlp_signed(&signature))
+    }
+
+    pub fn decode_raw_transaction(raw: &str) -> Result<(TypedTransaction, Signature), Box<dyn Error>> {
+        let bytes = Bytes::from_str(raw.trim()).map_err(|_| "Invalid raw transaction hex")?;
+        let (mut tx, signature) = TypedTransaction::decode_signed(&Rlp::new(&bytes))
+            .map_err(|e| format!("Invalid raw transaction: {}", e))?;
+        let from = signature.recover(tx.sighash())?;
+        tx.set_from(from);
+        Ok((tx, signature))
+    }
+
+    pub fn read_file<T: DeserializeOwned>(path: &str) -> Result<T, Box<dyn Error>> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+        let file: T = serde_json::from_str(&data).map_err(|e| format!("Failed to parse '{}': {}", path, e))?;
+        Ok(file)
+    }
+
+    pub fn write_file<T: Serialize>(path: &str, file: &T) -> Result<(), Box<dyn Error>> {
+        let data = serde_json::to_string_pretty(file)?;
+        fs::write(path, data).map_err(|e| format!("Failed to write '{}': {}", path, e))?;
+        Ok(())
+    }
+
+    pub fn check_version(version: u32) -> Result<(), Box<dyn Error>> {
+        if version != TX_FILE_VERSION {
+            return Err(format!("Unsupported transaction file version {} (expected {})", version, TX_FILE_VERSION).into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::Signer;
+
+    const TEST_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn params<'a>() -> OfflineTxParams<'a> {
+        OfflineTxParams {
+            to: "0x000000000000000000000000000000000000dEaD",
+            value: "1000",
+            nonce: 7,
+            chain_id: 11155111,
+            gas_limit: "21000",
+            gas_price: None,
+            max_fee: Some("30000000000"),
+            max_priority_fee: Some("1000000000"),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn test_sign_and_decode_round_trip() {
+        let wallet = LocalWallet::from_str(TEST_KEY).unwrap();
+        let tx = OfflineService::build_transaction(&params(), wallet.address()).unwrap();
+        let raw = OfflineService::sign(&tx, &wallet).unwrap();
+
+        let (decoded, _) = OfflineService::decode_raw_transaction(&format!("{}", raw)).unwrap();
+        assert_eq!(decoded.from(), Some(&wallet.address()));
+        assert_eq!(decoded.nonce(), Some(&U256::from(7)));
+        assert_eq!(decoded.chain_id().map(|id| id.as_u64()), Some(11155111));
+        assert_eq!(decoded.value(), Some(&U256::from(1000)));
+    }
+
+    #[test]
+    fn test_unsigned_file_round_trip() {
+        let wallet = LocalWallet::from_str(TEST_KEY).unwrap();
+        let tx = OfflineService::build_transaction(&params(), wallet.address()).unwrap();
+        let summary = TxSummary {
+            network_name: "ethereum_sepolia".to_string(),
+            chain_id: 11155111,
+            native_token: "ETH".to_string(),
+            from: wallet.address(),
+            to: tx.to_addr().copied(),
+            to_name: None,
+            to_kind: None,
+            amount: U256::from(1000),
+            asset: AssetInfo {
+                symbol: "ETH".to_string(),
+                decimals: 18,
+                contract: None,
+            },
+            call: None,
+            gas_limit: U256::from(21000),
+            fee_low: U256::zero(),
+            fee_high: U256::zero(),
+            warnings: Vec::new(),
+        };
+
+        let file = UnsignedTxFile::from_transaction(&tx, &summary).unwrap();
+        let json = serde_json::to_string(&file).unwrap();
+        let file: UnsignedTxFile = serde_json::from_str(&json).unwrap();
+        let rebuilt = OfflineService::build_transaction(&file.params(), wallet.address()).unwrap();
+
+        assert_eq!(rebuilt.sighash(), tx.sighash());
+        assert_eq!(file.summary(&rebuilt).unwrap().fee_high, U256::from(21000u64) * U256::from(30_000_000_000u64));
+    }
+
+    fn file_for(tx: &TypedTransaction, contract: Option<Address>, recipient: Address, amount: u64) -> UnsignedTxFile {
+        let summary = TxSummary {
+            network_name: "ethereum_sepolia".to_string(),
+            chain_id: 11155111,
+            native_token: "ETH".to_string(),
+            from: *tx.from().unwrap(),
+            to: Some(recipient),
+            to_name: None,
+            to_kind: None,
+            amount: U256::from(amount),
+            asset: AssetInfo {
+                symbol: if contract.is_some() { "USDC" } else { "ETH" }.to_string(),
+                decimals: if contract.is_some() { 6 } else { 18 },
+                contract,
+            },
+            call: None,
+            gas_limit: U256::from(21000),
+            fee_low: U256::zero(),
+            fee_high: U256::zero(),
+            warnings: Vec::new(),
+        };
+        UnsignedTxFile::from_transaction(tx, &summary).unwrap()
+    }
+
+    fn signed_view(file: &UnsignedTxFile, from: Address) -> Result<TxSummary, Box<dyn Error>> {
+        let tx = OfflineService::build_transaction(&file.params(), from)?;
+        file.summary(&tx)
+    }
+
+    #[test]
+    fn test_summary_rejects_asset_block_that_differs_from_transaction() {
+        let wallet = LocalWallet::from_str(TEST_KEY).unwrap();
+        let tx = OfflineService::build_transaction(&params(), wallet.address()).unwrap();
+        let dead = Address::from_low_u64_be(0xdead);
+
+        let mut file = file_for(&tx, None, dead, 1000);
+        assert!(signed_view(&file, wallet.address()).is_ok());
+
+        file.transaction.to = "0x0000000000000000000000000000000000000bad".to_string();
+        assert!(signed_view(&file, wallet.address()).err().unwrap().to_string().contains("inconsistent"));
+
+        let mut file = file_for(&tx, None, dead, 1000);
+        file.transaction.value = "5000000000000000000".to_string();
+        assert!(signed_view(&file, wallet.address()).err().unwrap().to_string().contains("inconsistent"));
+
+        // Displayed as 1000 wei to 0xdead, but the calldata moves tokens to someone else.
+        let mut file = file_for(&tx, None, dead, 1000);
+        file.transaction.value = "0".to_string();
+        file.transaction.data = format!("0xa9059cbb{:0>64}{:0>64}", "bad", "3e8");
+        assert!(signed_view(&file, wallet.address()).err().unwrap().to_string().contains("inconsistent"));
+
+        let mut file = file_for(&tx, None, dead, 1000);
+        file.transaction.data = "0xdeadbeef".to_string();
+        assert!(signed_view(&file, wallet.address()).err().unwrap().to_string().contains("not a native or ERC-20 transfer"));
+    }
+
+    #[test]
+    fn test_summary_decodes_token_transfer() {
+        let wallet = LocalWallet::from_str(TEST_KEY).unwrap();
+        let token = Address::from_low_u64_be(0x70);
+        let recipient = Address::from_low_u64_be(0xdead);
+        let mut token_params = params();
+        let data = format!("0xa9059cbb{:0>64}{:0>64}", "dead", "1f4");
+        token_params.to = "0x0000000000000000000000000000000000000070";
+        token_params.value = "0";
+        token_params.data = Some(&data);
+        let tx = OfflineService::build_transaction(&token_params, wallet.address()).unwrap();
+
+        let file = file_for(&tx, Some(token), recipient, 500);
+        let summary = signed_view(&file, wallet.address()).unwrap();
+        assert_eq!(summary.to, Some(recipient));
+        assert_eq!(summary.amount, U256::from(500));
+        assert_eq!(summary.asset.contract, Some(token));
+        assert!(summary.warnings.iter().any(|warning| warning.contains("cannot be verified offline")));
+
+        let mut file = file_for(&tx, Some(token), recipient, 1);
+        file.asset.amount = "1".to_string();
+        assert!(signed_view(&file, wallet.address()).is_err());
+    }
+
+    #[test]
+    fn test_build_requires_complete_fees() {
+        let mut incomplete = params();
+        incomplete.max_priority_fee = None;
+        assert!(OfflineService::build_transaction(&incomplete, Address::zero()).is_err());
+    }
+}
@@ -0,0 +1,379 @@
+/*
+    SpendingPolicy - per-account guardrails checked before a transaction is signed.
+    - Read from storage/<account>/policy.json; without the file nothing is restricted, a malformed file blocks signing.
+    - Limits are set per asset ("native" or a token contract address) in human units: a per-transaction maximum and
+      a rolling 24-hour total that includes pending and successful outgoing transfers from history. Without the
+      network's history the daily limit cannot be checked and counts as broken.
+    - Recipients can be allowlisted or denylisted, and contract interactions (calls, approval spenders) limited to an
+      allowlist. Entries are addresses or address book labels.
+    - Transactions given as fields or calldata (offline signing, contract calls) are decoded: ERC-20 `transfer` is
+      checked as a token transfer to the decoded recipient, `approve` against the spender, and any other calldata
+      against the called contract.
+*/
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::core::types::{Address, U256};
+use serde::Deserialize;
+use crate::config::STORAGE_DIR;
+use crate::services::address::{checksum, parse_address};
+use crate::services::address_book::AddressBook;
+use crate::services::contract::{ContractService, TokenCall};
+use crate::services::history::{StoredTransaction, TxStatus};
+use crate::services::units::{format_units, parse_units};
+
+pub const NATIVE_ASSET: &str = "native";
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct AssetLimit {
+    pub per_tx: Option<String>,
+    pub daily: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SpendingPolicy {
+    #[serde(default)]
+    pub limits: HashMap<String, AssetLimit>,
+    #[serde(default)]
+    pub recipient_allowlist: Vec<String>,
+    #[serde(default)]
+    pub recipient_denylist: Vec<String>,
+    #[serde(default)]
+    pub contract_allowlist: Vec<String>,
+}
+
+/// What a transaction spends and who it reaches, as checked against the policy.
+pub struct SpendRequest {
+    pub recipient: Option<Address>,
+    pub contract: Option<Address>,
+    /// Token contract; none for the native token.
+    pub asset: Option<Address>,
+    pub amount: U256,
+    /// None for a token whose decimals are unknown; its limits then cannot be checked and count as broken.
+    pub decimals: Option<u32>,
+    pub symbol: String,
+}
+
+impl SpendRequest {
+    /// What a transaction spends and reaches, decoded from its fields. `token` is the symbol and decimals of the
+    /// called contract when it is a token.
+    pub fn from_transaction(tx: &TypedTransaction, native_token: &str, token: Option<(String, u32)>) -> Vec<Self> {
+        let target = tx.to_addr().copied();
+        let value = tx.value().copied().unwrap_or_default();
+        let data = tx.data().cloned().unwrap_or_default();
+        let native = |recipient: Option<Address>, contract: Option<Address>| SpendRequest {
+            recipient,
+            contract,
+            asset: None,
+            amount: value,
+            decimals: Some(18),
+            symbol: native_token.to_string(),
+        };
+
+        if data.is_empty() {
+            return vec![native(target, None)];
+        }
+        match ContractService::decode_token_call(&data) {
+            Some(TokenCall::Transfer { to, amount }) => {
+                let (symbol, decimals) = match token {
+                    Some((symbol, decimals)) => (symbol, Some(decimals)),
+                    None => (target.as_ref().map(checksum).unwrap_or_default(), None),
+                };
+                let mut requests = vec![SpendRequest {
+                    recipient: Some(to),
+                    contract: target,
+                    asset: target,
+                    amount,
+                    decimals,
+                    symbol,
+                }];
+                if !value.is_zero() {
+                    requests.push(native(None, target));
+                }
+                requests
+            }
+            Some(TokenCall::Approve { spender, .. }) => vec![native(None, Some(spender))],
+            None => vec![native(None, target)],
+        }
+    }
+}
+
+impl SpendingPolicy {
+    pub fn path(account_name: &str) -> PathBuf {
+        Path::new(STORAGE_DIR).join(account_name).join("policy.json")
+    }
+
+    /// Returns none when the account has no policy file.
+    pub fn load(account_name: &str) -> Result<Option<Self>, Box<dyn Error>> {
+        let path = Self::path(account_name);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let policy = serde_json::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| format!("Invalid spending policy {:?}: {}", path, e))?;
+        Ok(Some(policy))
+    }
+
+    pub fn limit(&self, asset: Option<Address>) -> Result<Option<&AssetLimit>, String> {
+        for (key, limit) in &self.limits {
+            let matches = match key.as_str() {
+                NATIVE_ASSET => asset.is_none(),
+                token => Some(parse_address(token, "policy asset")?) == asset,
+            };
+            if matches {
+                return Ok(Some(limit));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns every rule the request breaks; `spent` is the amount of the asset already sent in the current window.
+    /// `spent` is the amount sent in the policy window, or none when the network's history is not available.
+    pub fn violations(
+        &self,
+        request: &SpendRequest,
+        spent: Option<U256>,
+        address_book: &AddressBook,
+    ) -> Result<Vec<String>, String> {
+        let mut violations = Vec::new();
+
+        match (self.limit(request.asset)?, request.decimals) {
+            (Some(limit), None) if limit.per_tx.is_some() || limit.daily.is_some() => {
+                violations.push(format!("Limits for {} cannot be checked: its decimals are unknown", request.symbol));
+            }
+            (Some(limit), Some(decimals)) => {
+                violations.extend(Self::limit_violations(limit, request, decimals, spent)?);
+            }
+            _ => {}
+        }
+
+        if let Some(recipient) = request.recipient {
+            if Self::resolve_entries(&self.recipient_denylist, address_book)?.contains(&recipient) {
+                violations.push(format!("Recipient {} is on the denylist", checksum(&recipient)));
+            }
+            let allowlist = Self::resolve_entries(&self.recipient_allowlist, address_book)?;
+            if !allowlist.is_empty() && !allowlist.contains(&recipient) {
+                violations.push(format!("Recipient {} is not on the allowlist", checksum(&recipient)));
+            }
+        }
+
+        if let Some(contract) = request.contract {
+            let allowlist = Self::resolve_entries(&self.contract_allowlist, address_book)?;
+            if !allowlist.is_empty() && !allowlist.contains(&contract) {
+                violations.push(format!("Contract {} is not on the contract allowlist", checksum(&contract)));
+            }
+        }
+
+        Ok(violations)
+    }
+
+    fn limit_violations(
+        limit: &AssetLimit,
+        request: &SpendRequest,
+        decimals: u32,
+        spent: Option<U256>,
+    ) -> Result<Vec<String>, String> {
+        let mut violations = Vec::new();
+        let amount = |value: U256| -> Result<String, String> {
+            Ok(format!("{} {}", format_units(value, decimals)?, request.symbol))
+        };
+
+        if let Some(per_tx) = &limit.per_tx {
+            let per_tx = parse_units(per_tx, decimals)?;
+            if request.amount > per_tx {
+                violations.push(format!(
+                    "Amount {} exceeds the per-transaction limit of {}",
+                    amount(request.amount)?,
+                    amount(per_tx)?
+                ));
+            }
+        }
+        if let Some(daily) = &limit.daily {
+            let daily = parse_units(daily, decimals)?;
+            match spent {
+                Some(spent) if spent.saturating_add(request.amount) > daily => violations.push(format!(
+                    "Sent in the last 24 hours would reach {}, above the daily limit of {}",
+                    amount(spent.saturating_add(request.amount))?,
+                    amount(daily)?
+                )),
+                Some(_) => {}
+                None => violations.push(format!(
+                    "Daily limit of {} cannot be checked without the network's transaction history",
+                    amount(daily)?
+                )),
+            }
+        }
+        Ok(violations)
+    }
+
+    fn resolve_entries(entries: &[String], address_book: &AddressBook) -> Result<Vec<Address>, String> {
+        entries
+            .iter()
+            .map(|entry| match address_book.resolve(entry) {
+                Some(address) => Ok(address),
+                None => parse_address(entry, "policy"),
+            })
+            .collect()
+    }
+
+    /// Total of the asset sent from `account` since the given unix time by transactions that did not fail.
+    pub fn spent_since(history: &[StoredTransaction], account: Address, asset: Option<Address>, since: u64) -> U256 {
+        history
+            .iter()
+            .filter(|tx| tx.direction(account) == "out")
+            .filter(|tx| tx.timestamp.is_some_and(|timestamp| timestamp >= since))
+            .filter(|tx| matches!(tx.status, TxStatus::Pending | TxStatus::Success | TxStatus::Unknown))
+            .filter_map(|tx| match asset {
+                None => Some(tx.value_as_u256()),
+                Some(token) if tx.kind.as_deref() == Some("token_transfer") && tx.token_contract_address() == Some(token) => {
+                    tx.token_value_as_u256()
+                }
+                Some(_) => None,
+            })
+            .fold(U256::zero(), |total, value| total.saturating_add(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238";
+
+    fn policy() -> SpendingPolicy {
+        serde_json::from_str(&format!(
+            r#"{{
+                "limits": {{"native": {{"per_tx": "0.5", "daily": "1"}}, "{}": {{"daily": "100"}}}},
+                "recipient_denylist": ["0x000000000000000000000000000000000000dEaD"],
+                "contract_allowlist": ["0x0000000000000000000000000000000000000010"]
+            }}"#,
+            TOKEN
+        ))
+        .unwrap()
+    }
+
+    fn request(recipient: u64, asset: Option<Address>, amount: U256, decimals: u32) -> SpendRequest {
+        SpendRequest {
+            recipient: Some(Address::from_low_u64_be(recipient)),
+            contract: None,
+            asset,
+            amount,
+            decimals: Some(decimals),
+            symbol: "TEST".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let policy = policy();
+        let book = AddressBook::load("");
+        let ether = U256::exp10(18);
+
+        assert!(policy.violations(&request(2, None, ether / 4, 18), Some(U256::zero()), &book).unwrap().is_empty());
+        let over = policy.violations(&request(2, None, ether, 18), Some(U256::zero()), &book).unwrap();
+        assert_eq!(over.len(), 1);
+        assert!(over[0].contains("per-transaction limit of 0.5 TEST"));
+        let daily = policy.violations(&request(2, None, ether / 2, 18), Some(ether * 3 / 4), &book).unwrap();
+        assert!(daily[0].contains("daily limit of 1 TEST"));
+        let unchecked = policy.violations(&request(2, None, ether / 4, 18), None, &book).unwrap();
+        assert_eq!(unchecked.len(), 1);
+        assert!(unchecked[0].contains("without the network's transaction history"));
+
+        let token = Some(parse_address(TOKEN, "token").unwrap());
+        let token_amount = U256::from(150_000_000u64);
+        assert_eq!(policy.violations(&request(2, token, token_amount, 6), Some(U256::zero()), &book).unwrap().len(), 1);
+        let other_token = Some(Address::from_low_u64_be(9));
+        assert!(policy.violations(&request(2, other_token, token_amount, 6), Some(U256::zero()), &book).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recipient_and_contract_lists() {
+        let policy = policy();
+        let book = AddressBook::load("");
+
+        let denied = policy.violations(&request(0xdead, None, U256::zero(), 18), Some(U256::zero()), &book).unwrap();
+        assert_eq!(denied, vec!["Recipient 0x000000000000000000000000000000000000dEaD is on the denylist"]);
+
+        let mut call = request(2, None, U256::zero(), 18);
+        call.recipient = None;
+        call.contract = Some(Address::from_low_u64_be(0x11));
+        assert_eq!(policy.violations(&call, Some(U256::zero()), &book).unwrap().len(), 1);
+        call.contract = Some(Address::from_low_u64_be(0x10));
+        assert!(policy.violations(&call, Some(U256::zero()), &book).unwrap().is_empty());
+
+        let allowlist: SpendingPolicy = serde_json::from_str(r#"{"recipient_allowlist": ["0x0000000000000000000000000000000000000002"]}"#).unwrap();
+        assert!(allowlist.violations(&request(2, None, U256::zero(), 18), Some(U256::zero()), &book).unwrap().is_empty());
+        assert_eq!(allowlist.violations(&request(3, None, U256::zero(), 18), Some(U256::zero()), &book).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_misspelled_limit_is_rejected() {
+        let misspelled = r#"{"limits": {"native": {"perTx": "0.5"}}}"#;
+        assert!(serde_json::from_str::<SpendingPolicy>(misspelled).is_err());
+        let misspelled = r#"{"limits": {"native": {"per_transaction": "0.5"}}}"#;
+        assert!(serde_json::from_str::<SpendingPolicy>(misspelled).is_err());
+    }
+
+    #[test]
+    fn test_requests_from_transaction_calldata() {
+        use ethers::core::types::TransactionRequest;
+
+        let policy = policy();
+        let book = AddressBook::load("");
+        let token = parse_address(TOKEN, "token").unwrap();
+        let transfer = |amount: &str| {
+            let data = hex::decode(format!("a9059cbb{:0>64}{:0>64}", "2", amount)).unwrap();
+            TypedTransaction::Legacy(TransactionRequest::new().to(token).data(data))
+        };
+
+        // 150 USDC through raw calldata hits the token's daily limit of 100.
+        let requests = SpendRequest::from_transaction(&transfer("8f0d180"), "ETH", Some(("USDC".to_string(), 6)));
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].recipient, Some(Address::from_low_u64_be(2)));
+        assert_eq!(requests[0].asset, Some(token));
+        assert_eq!(requests[0].contract, Some(token));
+        assert!(policy.violations(&requests[0], Some(U256::zero()), &book).unwrap().iter().any(|v| v.contains("daily limit")));
+
+        // Without decimals the token limit cannot be checked and blocks.
+        let requests = SpendRequest::from_transaction(&transfer("1"), "ETH", None);
+        let violations = policy.violations(&requests[0], Some(U256::zero()), &book).unwrap();
+        assert!(violations.iter().any(|v| v.contains("decimals are unknown")));
+
+        let approve = hex::decode(format!("095ea7b3{:0>64}{:0>64}", "11", "1")).unwrap();
+        let approve = TypedTransaction::Legacy(TransactionRequest::new().to(token).data(approve));
+        let requests = SpendRequest::from_transaction(&approve, "ETH", None);
+        assert_eq!(requests[0].contract, Some(Address::from_low_u64_be(0x11)));
+        assert_eq!(policy.violations(&requests[0], Some(U256::zero()), &book).unwrap().len(), 1);
+
+        let call = TypedTransaction::Legacy(TransactionRequest::new().to(Address::from_low_u64_be(0x12)).data(vec![1, 2, 3, 4]));
+        let requests = SpendRequest::from_transaction(&call, "ETH", None);
+        assert_eq!((requests[0].recipient, requests[0].contract), (None, Some(Address::from_low_u64_be(0x12))));
+        assert_eq!(policy.violations(&requests[0], Some(U256::zero()), &book).unwrap().len(), 1);
+
+        let native = TypedTransaction::Legacy(TransactionRequest::new().to(Address::from_low_u64_be(2)).value(U256::exp10(18)));
+        let requests = SpendRequest::from_transaction(&native, "ETH", None);
+        assert_eq!((requests[0].asset, requests[0].contract), (None, None));
+        assert!(policy.violations(&requests[0], Some(U256::zero()), &book).unwrap()[0].contains("per-transaction limit"));
+    }
+
+    #[test]
+    fn test_spent_since() {
+        let account = Address::from_low_u64_be(1);
+        let data = r#"[
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x0","gas_price":"0x0","value":"0x64","token_value":null,"status":"success","timestamp":1000},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x0","gas_price":"0x0","value":"0x64","token_value":null,"status":"failed","timestamp":1000},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x0","gas_price":"0x0","value":"0x64","token_value":null,"status":"pending","timestamp":10},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000002","to":"0x0000000000000000000000000000000000000001","gas":"0x0","gas_price":"0x0","value":"0x64","token_value":null,"status":"success","timestamp":1000},
+            {"type":"Eip1559","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000070","gas":"0x0","gas_price":"0x0","value":"0x0","token_value":"500","kind":"token_transfer","token_contract":"0x0000000000000000000000000000000000000070","status":"success","timestamp":1000}
+        ]"#;
+        let history: Vec<StoredTransaction> = serde_json::from_str(data).unwrap();
+
+        assert_eq!(SpendingPolicy::spent_since(&history, account, None, 500), U256::from(100));
+        let token = Some(Address::from_low_u64_be(0x70));
+        assert_eq!(SpendingPolicy::spent_since(&history, account, token, 500), U256::from(500));
+    }
+}
@@ -0,0 +1,197 @@
+/*
+    SelectorRegistry - decoding calldata by its 4-byte function selector and event logs by their topic.
+    - Bundled signatures (config::KNOWN_FUNCTION_SIGNATURES, KNOWN_EVENT_SIGNATURES) cover ERC-20/721/1155, WETH
+      and Uniswap routers and pools.
+    - User ABIs (JSON files in storage/<account>/abis and `--abi` files) are consulted before the bundled ones.
+    - A selector or topic matches only if the data decodes with its parameter types.
+*/
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use ethers::abi::{Abi, AbiParser, Event, Function, Log as DecodedLog, RawLog, Token};
+use ethers::core::types::Log;
+use crate::config::{KNOWN_EVENT_SIGNATURES, KNOWN_FUNCTION_SIGNATURES, STORAGE_DIR};
+use crate::services::contract::ContractService;
+
+pub struct SelectorRegistry {
+    functions: Vec<Function>,
+    events: Vec<Event>,
+}
+
+impl SelectorRegistry {
+    pub fn bundled() -> Self {
+        let functions = AbiParser::default().parse(KNOWN_FUNCTION_SIGNATURES).unwrap_or_default();
+        // Parsed one by one: events sharing a name are kept in declaration order.
+        let events = KNOWN_EVENT_SIGNATURES
+            .iter()
+            .filter_map(|signature| AbiParser::default().parse_event(signature).ok())
+            .collect();
+        SelectorRegistry {
+            functions: functions.functions().cloned().collect(),
+            events,
+        }
+    }
+
+    /// Bundled signatures plus the account's ABI directory and the given ABI files.
+    pub fn load(account_name: &str, abi_paths: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut registry = Self::bundled();
+
+        let abi_dir = Path::new(STORAGE_DIR).join(account_name).join("abis");
+        if let Ok(entries) = fs::read_dir(&abi_dir) {
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.extension().is_some_and(|extension| extension == "json") {
+                    match ContractService::load_abi(&path.to_string_lossy()) {
+                        Ok(abi) => registry.add_abi(&abi),
+                        Err(e) => println!("Skipping ABI {:?}: {}", path, e),
+                    }
+                }
+            }
+        }
+
+        for path in abi_paths {
+            registry.add_abi(&ContractService::load_abi(path)?);
+        }
+        Ok(registry)
+    }
+
+    pub fn add_abi(&mut self, abi: &Abi) {
+        let mut functions: Vec<Function> = abi.functions().cloned().collect();
+        functions.append(&mut self.functions);
+        self.functions = functions;
+
+        let mut events: Vec<Event> = abi.events().cloned().collect();
+        events.append(&mut self.events);
+        self.events = events;
+    }
+
+    /// Returns the first function whose selector matches and whose parameters decode the calldata.
+    pub fn decode_call(&self, data: &[u8]) -> Option<(&Function, Vec<Token>)> {
+        let (selector, arguments) = (data.get(..4)?, &data[4..]);
+        self.functions
+            .iter()
+            .filter(|function| function.short_signature() == selector)
+            .find_map(|function| function.decode_input(arguments).ok().map(|tokens| (function, tokens)))
+    }
+
+    /// Returns the first event whose topic matches and whose indexed and data parameters decode the log.
+    pub fn decode_log(&self, log: &Log) -> Option<(&Event, DecodedLog)> {
+        let topic = log.topics.first()?;
+        self.events
+            .iter()
+            .filter(|event| !event.anonymous && event.signature() == *topic)
+            .find_map(|event| {
+                let raw = RawLog {
+                    topics: log.topics.clone(),
+                    data: log.data.to_vec(),
+                };
+                event.parse_log(raw).ok().map(|decoded| (event, decoded))
+            })
+    }
+
+    pub fn describe_arguments(function: &Function, tokens: &[Token]) -> Vec<String> {
+        function
+            .inputs
+            .iter()
+            .zip(tokens)
+            .enumerate()
+            .map(|(index, (param, token))| {
+                let name = if param.name.is_empty() { format!("arg{}", index) } else { param.name.clone() };
+                format!("{} ({}): {}", name, param.kind, ContractService::format_token(token))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::Address;
+    use ethers::core::types::{H256, U256};
+
+    #[test]
+    fn test_bundled_signatures_parse() {
+        let structs = KNOWN_FUNCTION_SIGNATURES.iter().filter(|line| line.starts_with("struct ")).count();
+        let registry = SelectorRegistry::bundled();
+        assert_eq!(registry.functions.len(), KNOWN_FUNCTION_SIGNATURES.len() - structs);
+        assert_eq!(registry.events.len(), KNOWN_EVENT_SIGNATURES.len());
+    }
+
+    #[test]
+    fn test_decode_known_selectors() {
+        let registry = SelectorRegistry::bundled();
+        let transfer = AbiParser::default().parse_function("transfer(address,uint256)").unwrap();
+        let data = transfer
+            .encode_input(&[Token::Address(Address::from_low_u64_be(2)), Token::Uint(U256::from(500))])
+            .unwrap();
+
+        let (function, tokens) = registry.decode_call(&data).unwrap();
+        assert_eq!(function.name, "transfer");
+        assert_eq!(
+            SelectorRegistry::describe_arguments(function, &tokens),
+            vec!["to (address): 0x0000000000000000000000000000000000000002", "amount (uint256): 500"]
+        );
+
+        let (function, _) = registry.decode_call(&hex::decode("d0e30db0").unwrap()).unwrap();
+        assert_eq!(function.name, "deposit");
+        let swap = registry.functions.iter().find(|f| f.name == "swapExactTokensForTokens").unwrap();
+        assert_eq!(hex::encode(swap.short_signature()), "38ed1739");
+        let exact_input_single = registry.functions.iter().find(|f| f.name == "exactInputSingle").unwrap();
+        assert_eq!(hex::encode(exact_input_single.short_signature()), "414bf389");
+    }
+
+    #[test]
+    fn test_decode_logs() {
+        let registry = SelectorRegistry::bundled();
+        let transfer_topic = H256::from(ethers::utils::keccak256("Transfer(address,address,uint256)"));
+        let from = H256::from(Address::from_low_u64_be(1));
+        let to = H256::from(Address::from_low_u64_be(2));
+        let mut amount = [0u8; 32];
+        U256::from(500).to_big_endian(&mut amount);
+
+        let erc20 = Log {
+            topics: vec![transfer_topic, from, to],
+            data: amount.to_vec().into(),
+            ..Default::default()
+        };
+        let (event, decoded) = registry.decode_log(&erc20).unwrap();
+        assert_eq!(event.inputs[2].name, "value");
+        assert_eq!(decoded.params[2].value, Token::Uint(U256::from(500)));
+
+        let erc721 = Log {
+            topics: vec![transfer_topic, from, to, H256::from_low_u64_be(7)],
+            ..Default::default()
+        };
+        let (event, decoded) = registry.decode_log(&erc721).unwrap();
+        assert_eq!(event.inputs[2].name, "tokenId");
+        assert_eq!(decoded.params[2].value, Token::Uint(U256::from(7)));
+
+        let unknown = Log {
+            topics: vec![H256::from_low_u64_be(1)],
+            ..Default::default()
+        };
+        assert!(registry.decode_log(&unknown).is_none());
+    }
+
+    #[test]
+    fn test_unknown_or_malformed_calldata() {
+        let registry = SelectorRegistry::bundled();
+        assert!(registry.decode_call(&hex::decode("12345678").unwrap()).is_none());
+        // transfer selector with truncated arguments
+        assert!(registry.decode_call(&hex::decode("a9059cbb0000").unwrap()).is_none());
+        assert!(registry.decode_call(&[0xa9, 0x05]).is_none());
+    }
+
+    #[test]
+    fn test_user_abi_takes_precedence() {
+        let mut registry = SelectorRegistry::bundled();
+        let abi = ContractService::parse_abi(
+            r#"[{"type":"function","name":"transfer","inputs":[{"name":"recipient","type":"address"},{"name":"value","type":"uint256"}],"outputs":[],"stateMutability":"nonpayable"}]"#,
+        )
+            .unwrap();
+        registry.add_abi(&abi);
+
+        let data = hex::decode(format!("a9059cbb{:0>64}{:0>64}", "2", "1f4")).unwrap();
+        let (function, _) = registry.decode_call(&data).unwrap();
+        assert_eq!(function.inputs[0].name, "recipient");
+    }
+}
@@ -0,0 +1,259 @@
+/*
+    SigningService - off-chain signatures with the logged-in wallet.
+    - EIP-191 personal messages (`personal_sign`), from text, a file or hex bytes.
+    - EIP-712 typed structured data, reviewed before signing.
+    - Signature verification recovers the signer address.
+*/
+use std::error::Error;
+use std::fs;
+use std::str::FromStr;
+use ethers::core::types::transaction::eip712::{Eip712, TypedData, Types};
+use ethers::core::types::{Address, Signature, U256};
+use ethers::signers::{LocalWallet, Signer};
+use serde_json::Value;
+use crate::services::address::{checksum, parse_address};
+use crate::services::confirmation::confirm_prompt;
+use crate::services::network::NetworkInfo;
+
+pub struct SigningService {
+    pub wallet: Option<LocalWallet>,
+    pub network: Option<NetworkInfo>,
+}
+
+impl SigningService {
+    pub fn new() -> Self {
+        SigningService {
+            wallet: None,
+            network: None,
+        }
+    }
+
+    pub fn set_wallet(&mut self, wallet: LocalWallet) {
+        self.wallet = Some(wallet);
+    }
+
+    pub fn set_network(&mut self, network: NetworkInfo) {
+        self.network = Some(network);
+    }
+
+    pub fn message_bytes(message: Option<&str>, file: Option<&str>, is_hex: bool) -> Result<Vec<u8>, Box<dyn Error>> {
+        let raw = match (message, file) {
+            (Some(message), None) => message.as_bytes().to_vec(),
+            (None, Some(path)) => fs::read(path).map_err(|e| format!("Failed to read '{}': {}", path, e))?,
+            _ => return Err("Provide either a message or --file".into()),
+        };
+
+        if is_hex {
+            let text = String::from_utf8(raw).map_err(|_| "Hex message is not valid text")?;
+            let text = text.trim();
+            hex::decode(text.strip_prefix("0x").unwrap_or(text)).map_err(|_| "Invalid hex message".into())
+        } else {
+            Ok(raw)
+        }
+    }
+
+    pub async fn sign_message(&self, message: &[u8]) -> Result<Signature, Box<dyn Error>> {
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let signature = wallet.sign_message(message).await?;
+
+        println!("Signer: {}", checksum(&wallet.address()));
+        println!("Signature: 0x{}", signature);
+        Ok(signature)
+    }
+
+    pub async fn sign_typed_data(&self, path: &str, skip_confirmation: bool) -> Result<Signature, Box<dyn Error>> {
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+        let typed_data: TypedData = serde_json::from_str(&data).map_err(|e| format!("Invalid EIP-712 typed data: {}", e))?;
+
+        Self::check_chain_id(typed_data.domain.chain_id, self.network.as_ref())?;
+        let digest = typed_data.encode_eip712().map_err(|e| format!("Invalid EIP-712 typed data: {}", e))?;
+
+        Self::print_typed_data(&typed_data);
+        println!("  Signer:             {}", checksum(&wallet.address()));
+        println!("  Digest:             0x{}", hex::encode(digest));
+        if !skip_confirmation {
+            confirm_prompt("Type 'yes' to sign this data: ")?;
+        }
+
+        let signature = wallet.sign_typed_data(&typed_data).await?;
+        println!("Signature: 0x{}", signature);
+        Ok(signature)
+    }
+
+    pub fn check_chain_id(domain_chain_id: Option<U256>, network: Option<&NetworkInfo>) -> Result<(), Box<dyn Error>> {
+        match (domain_chain_id, network) {
+            (Some(chain_id), Some(network)) if chain_id != U256::from(network.chain_id) => Err(format!(
+                "Typed data is for chain ID {}, but the current network '{}' has chain ID {}",
+                chain_id, network.name, network.chain_id
+            ).into()),
+            (Some(chain_id), None) => {
+                println!("Warning: no network selected; cannot check the domain chain ID {}.", chain_id);
+                Ok(())
+            }
+            (None, _) => {
+                println!("Warning: the domain has no chain ID; the signature may be valid on any chain.");
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn print_typed_data(typed_data: &TypedData) {
+        let domain = &typed_data.domain;
+        println!("Please review the typed data:");
+        println!("  Domain:");
+        if let Some(name) = &domain.name {
+            println!("    Name:             {}", name);
+        }
+        if let Some(version) = &domain.version {
+            println!("    Version:          {}", version);
+        }
+        if let Some(chain_id) = &domain.chain_id {
+            println!("    Chain ID:         {}", chain_id);
+        }
+        if let Some(contract) = &domain.verifying_contract {
+            println!("    Contract:         {}", checksum(contract));
+        }
+        if let Some(salt) = &domain.salt {
+            println!("    Salt:             0x{}", hex::encode(salt));
+        }
+        println!("  Message ({}):", typed_data.primary_type);
+        let message = Value::Object(typed_data.message.clone().into_iter().collect());
+        Self::print_struct(&typed_data.types, &typed_data.primary_type, &message, 4);
+    }
+
+    fn print_struct(types: &Types, type_name: &str, value: &Value, indent: usize) {
+        let Some(fields) = types.get(type_name) else {
+            println!("{:indent$}{}", "", value, indent = indent);
+            return;
+        };
+
+        for field in fields {
+            let field_value = value.get(&field.name).unwrap_or(&Value::Null);
+            let base_type = field.r#type.split('[').next().unwrap_or_default();
+            if types.contains_key(base_type) {
+                println!("{:indent$}{} ({}):", "", field.name, field.r#type, indent = indent);
+                match field_value {
+                    Value::Array(items) => {
+                        for (index, item) in items.iter().enumerate() {
+                            println!("{:indent$}[{}]:", "", index, indent = indent + 2);
+                            Self::print_struct(types, base_type, item, indent + 4);
+                        }
+                    }
+                    _ => Self::print_struct(types, base_type, field_value, indent + 2),
+                }
+            } else {
+                let rendered = match field_value {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                println!("{:indent$}{} ({}): {}", "", field.name, field.r#type, rendered, indent = indent);
+            }
+        }
+    }
+
+    pub fn recover_signer(message: &[u8], signature: &str) -> Result<Address, Box<dyn Error>> {
+        let signature = Signature::from_str(signature.trim()).map_err(|_| "Invalid signature format")?;
+        Ok(signature.recover(message.to_vec())?)
+    }
+
+    pub fn verify_message(message: &[u8], signature: &str, expected: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        let signer = Self::recover_signer(message, signature)?;
+        println!("Recovered signer: {}", checksum(&signer));
+
+        match expected {
+            Some(expected) => {
+                let expected = parse_address(expected, "expected signer")?;
+                if signer == expected {
+                    println!("Signature is valid for {}.", checksum(&expected));
+                    Ok(true)
+                } else {
+                    println!("Signature does NOT match {}.", checksum(&expected));
+                    Ok(false)
+                }
+            }
+            None => Ok(true),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[tokio::test]
+    async fn test_sign_and_recover_message() {
+        let wallet = LocalWallet::from_str(TEST_KEY).unwrap();
+        let mut service = SigningService::new();
+        service.set_wallet(wallet.clone());
+
+        let signature = service.sign_message(b"I own this address").await.unwrap();
+        let signer = SigningService::recover_signer(b"I own this address", &signature.to_string()).unwrap();
+        assert_eq!(signer, wallet.address());
+
+        let other = SigningService::recover_signer(b"Something else", &signature.to_string()).unwrap();
+        assert_ne!(other, wallet.address());
+    }
+
+    const MAIL_TYPED_DATA: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                {"name": "name", "type": "string"},
+                {"name": "version", "type": "string"},
+                {"name": "chainId", "type": "uint256"},
+                {"name": "verifyingContract", "type": "address"}
+            ],
+            "Person": [{"name": "name", "type": "string"}, {"name": "wallet", "type": "address"}],
+            "Mail": [
+                {"name": "from", "type": "Person"},
+                {"name": "to", "type": "Person"},
+                {"name": "contents", "type": "string"}
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": {"name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"},
+            "to": {"name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"},
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    fn network(chain_id: u64) -> NetworkInfo {
+        NetworkInfo {
+            name: "test".to_string(),
+            url: None,
+            native_token: "ETH".to_string(),
+            chain_id,
+            ens_registry: None,
+        }
+    }
+
+    #[test]
+    fn test_typed_data_digest_and_chain_check() {
+        let typed_data: TypedData = serde_json::from_str(MAIL_TYPED_DATA).unwrap();
+        let digest = typed_data.encode_eip712().unwrap();
+        assert_eq!(
+            hex::encode(digest),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+
+        assert!(SigningService::check_chain_id(typed_data.domain.chain_id, Some(&network(1))).is_ok());
+        assert!(SigningService::check_chain_id(typed_data.domain.chain_id, Some(&network(137))).is_err());
+    }
+
+    #[test]
+    fn test_hex_message_bytes() {
+        let bytes = SigningService::message_bytes(Some("0xdeadbeef"), None, true).unwrap();
+        assert_eq!(bytes, vec![0xde, 0xad, 0xbe, 0xef]);
+        assert!(SigningService::message_bytes(None, None, false).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+/*
+    SyncService - importing transfers to and from the account into history.
+    - ERC-20 and ERC-721 `Transfer` logs are fetched with eth_getLogs in chunked block ranges.
+    - Incoming native transfers come from trace_filter, which only some RPC providers support.
+    - The last synced block is checkpointed per account and network so the next sync resumes from there.
+*/
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use ethers::core::types::{Address, Log, H256, U256};
+use ethers::core::types::{Action, Trace};
+use ethers::utils::keccak256;
+use serde::{Deserialize, Serialize};
+use crate::services::history::{StoredTransaction, TxStatus};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SyncCheckpoint {
+    pub last_block: Option<u64>,
+}
+
+impl SyncCheckpoint {
+    pub fn path(history_file: &Path) -> PathBuf {
+        history_file.with_file_name("sync_checkpoint.json")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(SyncCheckpoint::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+pub struct SyncService;
+
+impl SyncService {
+    pub fn transfer_topic() -> H256 {
+        H256::from(keccak256("Transfer(address,address,uint256)"))
+    }
+
+    pub fn address_topic(address: Address) -> H256 {
+        H256::from(address)
+    }
+
+    /// Splits `start..=end` into inclusive ranges of at most `size` blocks.
+    pub fn chunk_ranges(start: u64, end: u64, size: u64) -> Vec<(u64, u64)> {
+        let size = size.max(1);
+        let mut ranges = Vec::new();
+        let mut from = start;
+        while from <= end {
+            let to = end.min(from.saturating_add(size - 1));
+            ranges.push((from, to));
+            if to == u64::MAX {
+                break;
+            }
+            from = to + 1;
+        }
+        ranges
+    }
+
+    /// Builds a history entry from an ERC-20 (three topics) or ERC-721 (four topics) `Transfer` log.
+    pub fn transfer_from_log(log: &Log, chain_id: u64) -> Option<StoredTransaction> {
+        if log.topics.first() != Some(&Self::transfer_topic()) || log.removed == Some(true) {
+            return None;
+        }
+        let from = Address::from(*log.topics.get(1)?);
+        let to = Address::from(*log.topics.get(2)?);
+
+        let (kind, token_value, token_id) = match log.topics.len() {
+            3 if log.data.len() >= 32 => ("token_transfer", U256::from_big_endian(&log.data[..32]), None),
+            4 => ("nft_transfer", U256::one(), Some(U256::from_big_endian(log.topics[3].as_bytes()))),
+            _ => return None,
+        };
+        let token = format!("{:?}", log.address);
+
+        Some(StoredTransaction {
+            kind: Some(kind.to_string()),
+            token_value: Some(token_value.to_string()),
+            token_contract: if token_id.is_none() { Some(token.clone()) } else { None },
+            token_id: token_id.map(|id| id.to_string()),
+            collection: token_id.map(|_| token),
+            ..Self::synced_entry(from, Some(to), U256::zero(), log.transaction_hash?, chain_id)
+        })
+    }
+
+    /// Builds a history entry from a successful call trace carrying value.
+    pub fn transfer_from_trace(trace: &Trace, chain_id: u64) -> Option<StoredTransaction> {
+        if trace.error.is_some() {
+            return None;
+        }
+        match &trace.action {
+            Action::Call(call) if !call.value.is_zero() => Some(StoredTransaction {
+                kind: Some("transfer".to_string()),
+                ..Self::synced_entry(call.from, Some(call.to), call.value, trace.transaction_hash?, chain_id)
+            }),
+            _ => None,
+        }
+    }
+
+    fn synced_entry(from: Address, to: Option<Address>, value: U256, hash: H256, chain_id: u64) -> StoredTransaction {
+        StoredTransaction {
+            tx_type: "Unknown".to_string(),
+            from: format!("{:?}", from),
+            to: to.map(|to| format!("{:?}", to)),
+            gas: "0x0".to_string(),
+            gas_price: "0x0".to_string(),
+            value: format!("{:#x}", value),
+            token_value: None,
+            hash: Some(format!("{:#x}", hash)),
+            nonce: None,
+            kind: None,
+            contract_address: None,
+            token_id: None,
+            collection: None,
+            chain_id: Some(chain_id),
+            token_contract: None,
+            token_symbol: None,
+            token_decimals: None,
+            timestamp: None,
+            status: TxStatus::Success,
+            fee_paid: None,
+        }
+    }
+
+    /// Two entries describe the same transfer when they share the hash, kind, parties and asset. Entries recorded
+    /// before token contracts were stored match on the rest.
+    pub fn same_transfer(a: &StoredTransaction, b: &StoredTransaction) -> bool {
+        a.hash_as_h256().is_some()
+            && a.hash_as_h256() == b.hash_as_h256()
+            && a.kind == b.kind
+            && a.sender_address() == b.sender_address()
+            && a.to_address() == b.to_address()
+            && (a.token_contract.is_none() || b.token_contract.is_none() || a.token_contract_address() == b.token_contract_address())
+            && a.token_id == b.token_id
+    }
+
+    /// Appends the entries that are not in history yet and returns how many were added.
+    pub fn merge(history: &mut Vec<StoredTransaction>, entries: Vec<StoredTransaction>) -> usize {
+        let mut added = 0;
+        for entry in entries {
+            if !history.iter().any(|existing| Self::same_transfer(existing, &entry)) {
+                history.push(entry);
+                added += 1;
+            }
+        }
+        added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::core::types::{Bytes, U64};
+
+    fn transfer_log(topics: Vec<H256>, data: Vec<u8>) -> Log {
+        Log {
+            address: Address::from_low_u64_be(0x70),
+            topics,
+            data: Bytes::from(data),
+            transaction_hash: Some(H256::from_low_u64_be(1)),
+            block_number: Some(U64::from(10)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_chunk_ranges() {
+        assert_eq!(SyncService::chunk_ranges(1, 5, 2), vec![(1, 2), (3, 4), (5, 5)]);
+        assert_eq!(SyncService::chunk_ranges(7, 7, 100), vec![(7, 7)]);
+        assert!(SyncService::chunk_ranges(8, 7, 100).is_empty());
+    }
+
+    #[test]
+    fn test_transfer_from_log() {
+        let from = SyncService::address_topic(Address::from_low_u64_be(1));
+        let to = SyncService::address_topic(Address::from_low_u64_be(2));
+        let mut amount = [0u8; 32];
+        U256::from(500).to_big_endian(&mut amount);
+
+        let erc20 = SyncService::transfer_from_log(
+            &transfer_log(vec![SyncService::transfer_topic(), from, to], amount.to_vec()),
+            1,
+        )
+            .unwrap();
+        assert_eq!(erc20.kind.as_deref(), Some("token_transfer"));
+        assert_eq!(erc20.token_value_as_u256(), Some(U256::from(500)));
+        assert_eq!(erc20.token_contract_address(), Some(Address::from_low_u64_be(0x70)));
+        assert_eq!(erc20.to_address(), Some(Address::from_low_u64_be(2)));
+
+        let token_id = H256::from_low_u64_be(42);
+        let erc721 = SyncService::transfer_from_log(
+            &transfer_log(vec![SyncService::transfer_topic(), from, to, token_id], Vec::new()),
+            1,
+        )
+            .unwrap();
+        assert_eq!(erc721.kind.as_deref(), Some("nft_transfer"));
+        assert_eq!(erc721.token_id.as_deref(), Some("42"));
+        assert!(erc721.token_contract.is_none());
+
+        assert!(SyncService::transfer_from_log(&transfer_log(vec![H256::zero(), from, to], amount.to_vec()), 1).is_none());
+    }
+
+    #[test]
+    fn test_merge_skips_duplicates() {
+        let from = SyncService::address_topic(Address::from_low_u64_be(1));
+        let to = SyncService::address_topic(Address::from_low_u64_be(2));
+        let log = transfer_log(vec![SyncService::transfer_topic(), from, to], vec![0u8; 32]);
+        let entry = SyncService::transfer_from_log(&log, 1).unwrap();
+
+        let mut history = Vec::new();
+        assert_eq!(SyncService::merge(&mut history, vec![entry.clone()]), 1);
+        assert_eq!(SyncService::merge(&mut history, vec![entry]), 0);
+        assert_eq!(history.len(), 1);
+    }
+}
@@ -0,0 +1,197 @@
+/*
+    TokenList - ERC-20 tokens tracked per account and network for the portfolio view.
+    - Stored in storage/<account>/<network>/tokens.json.
+    - Tokens are added by address (symbol and decimals are read from the contract) or imported from a token list in
+      the Uniswap format (https://tokenlists.org), keeping only the entries for the network's chain ID. Entries with
+      an invalid address or more than 77 decimals are skipped.
+    - Tokens are removed by address or symbol.
+*/
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use ethers::core::types::Address;
+use serde::{Deserialize, Serialize};
+use crate::config::STORAGE_DIR;
+use crate::services::address::checksum;
+use crate::services::units::MAX_DECIMALS;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackedToken {
+    pub address: String,
+    pub symbol: String,
+    pub decimals: u32,
+    pub name: Option<String>,
+}
+
+impl TrackedToken {
+    pub fn contract_address(&self) -> Option<Address> {
+        Address::from_str(&self.address).ok()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TokenList {
+    pub tokens: Vec<TrackedToken>,
+}
+
+/// A token list in the Uniswap format; only the fields Vaulty uses.
+#[derive(Deserialize, Debug)]
+pub struct UniswapTokenList {
+    pub name: Option<String>,
+    pub tokens: Vec<UniswapToken>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UniswapToken {
+    pub chain_id: u64,
+    pub address: String,
+    pub name: Option<String>,
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub already_tracked: usize,
+    pub other_chains: usize,
+    pub invalid: usize,
+}
+
+impl TokenList {
+    pub fn path(account_name: &str, network_name: &str) -> PathBuf {
+        Path::new(STORAGE_DIR).join(account_name).join(network_name).join("tokens.json")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(TokenList::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn contains(&self, address: Address) -> bool {
+        self.tokens.iter().any(|token| token.contract_address() == Some(address))
+    }
+
+    /// Finds a tracked token by address or symbol (case-insensitive).
+    pub fn find(&self, token: &str) -> Option<&TrackedToken> {
+        self.position(token).map(|index| &self.tokens[index])
+    }
+
+    fn position(&self, token: &str) -> Option<usize> {
+        let address = Address::from_str(token.trim()).ok();
+        self.tokens.iter().position(|tracked| match address {
+            Some(address) => tracked.contract_address() == Some(address),
+            None => tracked.symbol.eq_ignore_ascii_case(token.trim()),
+        })
+    }
+
+    /// Adds the token unless its address is already tracked; returns whether it was added.
+    pub fn add(&mut self, token: TrackedToken) -> bool {
+        match token.contract_address() {
+            Some(address) if !self.contains(address) => {
+                self.tokens.push(token);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes the token with the given address or symbol (case-insensitive).
+    pub fn remove(&mut self, token: &str) -> Option<TrackedToken> {
+        let index = self.position(token)?;
+        Some(self.tokens.remove(index))
+    }
+
+    pub fn import(&mut self, list: &UniswapTokenList, chain_id: u64) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+        for token in &list.tokens {
+            if token.chain_id != chain_id {
+                summary.other_chains += 1;
+                continue;
+            }
+            let Ok(address) = Address::from_str(&token.address) else {
+                summary.invalid += 1;
+                continue;
+            };
+            if token.decimals > MAX_DECIMALS {
+                summary.invalid += 1;
+                continue;
+            }
+            let added = self.add(TrackedToken {
+                address: checksum(&address),
+                symbol: token.symbol.clone(),
+                decimals: token.decimals,
+                name: token.name.clone(),
+            });
+            if added {
+                summary.added += 1;
+            } else {
+                summary.already_tracked += 1;
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST: &str = r#"{
+        "name": "Test List",
+        "timestamp": "2024-01-01T00:00:00.000Z",
+        "version": {"major": 1, "minor": 0, "patch": 0},
+        "tokens": [
+            {"chainId": 1, "address": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", "name": "USD Coin", "symbol": "USDC", "decimals": 6, "logoURI": "https://example.com/usdc.png"},
+            {"chainId": 1, "address": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", "name": "USD Coin", "symbol": "USDC", "decimals": 6},
+            {"chainId": 10, "address": "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85", "name": "USD Coin", "symbol": "USDC", "decimals": 6},
+            {"chainId": 1, "address": "not-an-address", "name": "Broken", "symbol": "BRK", "decimals": 18},
+            {"chainId": 1, "address": "0x0000000000000000000000000000000000000071", "name": "Huge", "symbol": "HUGE", "decimals": 255}
+        ]
+    }"#;
+
+    #[test]
+    fn test_import_uniswap_list() {
+        let list: UniswapTokenList = serde_json::from_str(LIST).unwrap();
+        let mut tokens = TokenList::default();
+
+        let summary = tokens.import(&list, 1);
+        assert_eq!(summary, ImportSummary { added: 1, already_tracked: 1, other_chains: 1, invalid: 2 });
+        assert_eq!(tokens.tokens[0].address, "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        assert_eq!(tokens.tokens[0].symbol, "USDC");
+        assert_eq!(tokens.tokens[0].decimals, 6);
+        assert_eq!(tokens.import(&list, 1).added, 0);
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut tokens = TokenList::default();
+        let token = TrackedToken {
+            address: "0x0000000000000000000000000000000000000070".to_string(),
+            symbol: "TKN".to_string(),
+            decimals: 18,
+            name: None,
+        };
+        assert!(tokens.add(token.clone()));
+        assert!(!tokens.add(token.clone()));
+
+        assert_eq!(tokens.find("Tkn"), Some(&token));
+        assert_eq!(tokens.remove("tkn"), Some(token.clone()));
+        assert!(tokens.tokens.is_empty());
+        tokens.add(token.clone());
+        assert_eq!(tokens.remove("0x0000000000000000000000000000000000000070"), Some(token));
+        assert!(tokens.remove("TKN").is_none());
+    }
+}
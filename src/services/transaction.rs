@@ -1,267 +1,1729 @@
 use std::sync::Arc;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
 use std::str::FromStr;
 use std::{fs, io};
 use std::path::{Path, PathBuf};
-use std::fs::OpenOptions;
-use std::io::{BufReader, BufWriter, Read, Write};
-use ethers::core::types::{Address, TransactionRequest, U256, H256};
+use ethers::core::types::{
+    Address, BlockId, BlockNumber, Bytes, Eip1559TransactionRequest, Filter, TraceFilter, TransactionReceipt,
+    TransactionRequest, U256, U64, H256,
+};
 use ethers::core::types::transaction::eip2718::TypedTransaction;
 use ethers::providers::{Http, Middleware, Provider, PendingTransaction};
 use ethers::signers::{LocalWallet, Signer};
-use ethers::types::{NameOrAddress, Signature};
+use ethers::types::Signature;
+use ethers::utils::keccak256;
 use ethers::contract::Contract;
-use ethers::abi::Abi;
-use serde::{Deserialize, Serialize};
-use crate::config::{STATE_FILE, STORAGE_DIR, ERC20_ABI};
-
-#[derive(Serialize, Deserialize, Debug)]
-struct StoredTransaction {
-    #[serde(rename = "type")]
-    tx_type: String,
-    from: String,
-    to: Option<String>,
-    gas: String,
-    gas_price: String,
-    value: String,
-    token_value: Option<String>,
+use ethers::abi::{parse_abi, Abi, StateMutability, Token};
+use serde_json::json;
+use crate::config::{
+    STATE_FILE, STORAGE_DIR, ERC20_ABI, ERC165_ABI, ERC721_ABI, ERC1155_ABI, OP_GAS_PRICE_ORACLE, OP_STACK_CHAIN_IDS,
+    SYNC_CHUNK_SIZE, SYNC_DEFAULT_LOOKBACK, FEE_HISTORY_BLOCKS, FEE_REWARD_PERCENTILES, NATIVE_TRANSFER_GAS,
+    ERC20_TRANSFER_GAS, POLICY_WINDOW_SECONDS,
+};
+use crate::services::address::{checksum, AccountKind, checksum_str, checksum_warning, parse_address, recipient_warnings};
+use crate::services::account::AccountService;
+use crate::services::address_book::AddressBook;
+use crate::services::batch::{BatchProgress, BatchRow, BatchService, SentRow};
+use crate::services::confirmation::{confirm_prompt, AssetInfo, TxSummary};
+use crate::services::contract::{ContractService, TokenCall};
+use crate::services::ens::EnsService;
+use crate::services::fees::{FeeEstimate, FeeService, FeeSpeed};
+use crate::services::history::{HistoryQuery, HistoryRecord, HistoryService, StoredTransaction, TxStatus};
+use crate::services::network::NetworkInfo;
+use crate::services::nft::{NftService, NftStandard, ERC1155_INTERFACE_ID, ERC721_INTERFACE_ID};
+use crate::services::nonce::NonceManager;
+use crate::services::selectors::SelectorRegistry;
+use crate::services::sync::{SyncCheckpoint, SyncService};
+use crate::services::policy::{SpendRequest, SpendingPolicy};
+use crate::services::offline::{
+    OfflineService, OfflineTxParams, SignedTxFile, TxFileNetwork, UnsignedTxFile, TX_FILE_VERSION,
+};
+use crate::services::tokens::{TokenList, TrackedToken, UniswapTokenList};
+use crate::services::units::{format_units, MAX_DECIMALS};
+
+pub struct TransactionService {
+    pub provider: Option<Arc<Provider<Http>>>,
+    pub wallet: Option<LocalWallet>,
+    pub network: Option<NetworkInfo>,
 }
 
-impl StoredTransaction {
-    fn from_address(&self) -> Option<Address> {
-        Address::from_str(&self.from).ok()
+impl TransactionService {
+    pub fn new() -> Self {
+        TransactionService {
+            provider: None,
+            wallet: None,
+            network: None,
+        }
+    }
+
+    pub fn set_provider(&mut self, provider_url: &str) {
+        let provider = Provider::<Http>::try_from(provider_url)
+            .expect("Invalid provider URL");
+        self.provider = Some(Arc::new(provider));
+    }
+
+    pub fn set_wallet(&mut self, wallet: LocalWallet) {
+        self.wallet = Some(wallet);
+    }
+
+    pub fn set_network(&mut self, network: NetworkInfo) {
+        self.network = Some(network);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send(
+        &mut self,
+        to: &str,
+        value: &str,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+        gas_limit: Option<&str>,
+        network_name: &str,
+        skip_confirmation: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let (typed_tx, summary) = self.build_transfer(to, value, None, gas_price, speed, gas_limit, None, network_name).await?;
+        summary.confirm(&self.address_book(), skip_confirmation)?;
+        self.enforce_policy(&[Self::transfer_request(&summary)], Some(network_name))?;
+
+        let tx_hash = self.sign_and_broadcast(&typed_tx).await?;
+        println!("Transaction sent. Hash: {:#x}", tx_hash);
+
+        self.save_history_to_file(&typed_tx, tx_hash, network_name).await;
+
+        Ok(format!("{:#x}", tx_hash))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_token(
+        &mut self,
+        to: &str,
+        value: &str,
+        token_address: &str,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+        gas_limit: Option<&str>,
+        network_name: &str,
+        skip_confirmation: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let (typed_tx, summary) = self
+            .build_transfer(to, value, Some(token_address), gas_price, speed, gas_limit, None, network_name)
+            .await?;
+        summary.confirm(&self.address_book(), skip_confirmation)?;
+        self.enforce_policy(&[Self::transfer_request(&summary)], Some(network_name))?;
+
+        let tx_hash = self.sign_and_broadcast(&typed_tx).await?;
+        println!("Token transfer sent. Hash: {:#x}", tx_hash);
+
+        self.save_history_to_file(&typed_tx, tx_hash, network_name).await;
+
+        Ok(format!("{:#x}", tx_hash))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn call_contract(
+        &mut self,
+        contract: &str,
+        signature: &str,
+        args: &[String],
+        abi_path: Option<&str>,
+        value: Option<&str>,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+        gas_limit: Option<&str>,
+        network_name: &str,
+        skip_confirmation: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let contract_address = parse_address(contract, "contract")?;
+        let function = ContractService::resolve_function(signature, abi_path)?;
+        let data = ContractService::encode_call(&function, args)?;
+        let value_in_wei = match value {
+            Some(v) => U256::from_dec_str(v).map_err(|_| "Invalid amount format")?,
+            None => U256::zero(),
+        };
+        if abi_path.is_some() && !value_in_wei.is_zero() && function.state_mutability != StateMutability::Payable {
+            return Err(format!("Function '{}' is not payable", function.name).into());
+        }
+
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+
+        let mut typed_tx: TypedTransaction = TransactionRequest::new()
+            .to(contract_address)
+            .value(value_in_wei)
+            .data(data)
+            .from(wallet.address())
+            .into();
+
+        let expected_fee_per_gas = self.apply_fees(&mut typed_tx, gas_price, speed).await?;
+        let (gas_limit_in_units, estimated_gas, chain_id) = self
+            .fill_transaction(&mut typed_tx, gas_limit, None, network_name)
+            .await?;
+
+        let summary = TxSummary {
+            network_name: network_name.to_string(),
+            chain_id,
+            native_token: self.native_token(),
+            from: wallet.address(),
+            to: Some(contract_address),
+            to_name: None,
+            to_kind: None,
+            amount: value_in_wei,
+            asset: AssetInfo {
+                symbol: self.native_token(),
+                decimals: 18,
+                contract: None,
+            },
+            call: Some(ContractService::describe_call(&function, args)),
+            gas_limit: gas_limit_in_units,
+            fee_low: estimated_gas * expected_fee_per_gas,
+            fee_high: gas_limit_in_units * Self::fee_per_gas(&typed_tx),
+            warnings: checksum_warning(contract).into_iter().collect(),
+        };
+        summary.confirm(&self.address_book(), skip_confirmation)?;
+        let token = match typed_tx.data().and_then(|data| ContractService::decode_token_call(data)) {
+            Some(TokenCall::Transfer { .. }) => self.token_metadata(contract_address).await,
+            _ => None,
+        };
+        let requests = SpendRequest::from_transaction(&typed_tx, &self.native_token(), token);
+        self.enforce_policy(&requests, Some(network_name))?;
+
+        let tx_hash = self.sign_and_broadcast(&typed_tx).await?;
+        println!("Contract call sent. Hash: {:#x}", tx_hash);
+
+        self.save_history_to_file(&typed_tx, tx_hash, network_name).await;
+
+        Ok(format!("{:#x}", tx_hash))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn deploy(
+        &mut self,
+        bytecode_path: &str,
+        args: &[String],
+        abi_path: Option<&str>,
+        value: Option<&str>,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+        gas_limit: Option<&str>,
+        network_name: &str,
+        skip_confirmation: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let bytecode = ContractService::load_bytecode(bytecode_path)?;
+        let (data, constructor_call) = match abi_path {
+            Some(path) => {
+                let abi = ContractService::load_abi(path)?;
+                ContractService::encode_constructor(&abi, bytecode, args)?
+            }
+            None if args.is_empty() => (bytecode, "constructor()".to_string()),
+            None => return Err("Constructor arguments require --abi".into()),
+        };
+        let value_in_wei = match value {
+            Some(v) => U256::from_dec_str(v).map_err(|_| "Invalid amount format")?,
+            None => U256::zero(),
+        };
+
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+
+        let mut typed_tx: TypedTransaction = TransactionRequest::new()
+            .value(value_in_wei)
+            .data(data)
+            .from(wallet.address())
+            .into();
+
+        let expected_fee_per_gas = self.apply_fees(&mut typed_tx, gas_price, speed).await?;
+        let (gas_limit_in_units, estimated_gas, chain_id) = self
+            .fill_transaction(&mut typed_tx, gas_limit, None, network_name)
+            .await?;
+
+        let summary = TxSummary {
+            network_name: network_name.to_string(),
+            chain_id,
+            native_token: self.native_token(),
+            from: wallet.address(),
+            to: None,
+            to_name: None,
+            to_kind: None,
+            amount: value_in_wei,
+            asset: AssetInfo {
+                symbol: self.native_token(),
+                decimals: 18,
+                contract: None,
+            },
+            call: Some(constructor_call),
+            gas_limit: gas_limit_in_units,
+            fee_low: estimated_gas * expected_fee_per_gas,
+            fee_high: gas_limit_in_units * Self::fee_per_gas(&typed_tx),
+            warnings: Vec::new(),
+        };
+        summary.confirm(&self.address_book(), skip_confirmation)?;
+        self.enforce_policy(&[Self::transfer_request(&summary)], Some(network_name))?;
+
+        let tx_hash = self.sign_and_broadcast(&typed_tx).await?;
+        println!("Deployment sent. Hash: {:#x}", tx_hash);
+        self.save_history_to_file(&typed_tx, tx_hash, network_name).await;
+        println!("Waiting for the transaction to be mined...");
+
+        let receipt = PendingTransaction::new(tx_hash, provider.provider())
+            .await?
+            .ok_or("Deployment transaction was dropped from the mempool")?;
+        self.record_receipt(&tx_hash, &receipt, network_name);
+
+        if receipt.status == Some(U64::zero()) {
+            return Err(format!("Deployment reverted in block {}", receipt.block_number.unwrap_or_default()).into());
+        }
+        let contract_address = receipt.contract_address.ok_or("Receipt has no contract address")?;
+        println!("Contract deployed at {} (block {})", checksum(&contract_address), receipt.block_number.unwrap_or_default());
+
+        Ok(checksum(&contract_address))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn approve(
+        &mut self,
+        token_address: &str,
+        spender: &str,
+        amount: &str,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+        gas_limit: Option<&str>,
+        network_name: &str,
+        skip_confirmation: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let token_address = parse_address(token_address, "token")?;
+        let spender_input = spender;
+        let spender = parse_address(spender, "spender")?;
+        let amount = match amount {
+            "unlimited" => U256::MAX,
+            amount => U256::from_dec_str(amount).map_err(|_| "Invalid amount format")?,
+        };
+
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+
+        let abi: Abi = serde_json::from_str(ERC20_ABI)?;
+        let contract = Contract::new(token_address, abi, provider.clone());
+        let tx = contract.method::<(Address, U256), bool>("approve", (spender, amount))?
+            .from(wallet.address());
+        let mut typed_tx = tx.tx;
+
+        let expected_fee_per_gas = self.apply_fees(&mut typed_tx, gas_price, speed).await?;
+        let (gas_limit_in_units, estimated_gas, chain_id) = self
+            .fill_transaction(&mut typed_tx, gas_limit, None, network_name)
+            .await?;
+
+        let address_book = self.address_book();
+        let asset = self.token_asset_info(token_address).await;
+        let allowance = if amount == U256::MAX {
+            "unlimited".to_string()
+        } else {
+            format!("{} {}", format_units(amount, asset.decimals)?, asset.symbol)
+        };
+
+        let summary = TxSummary {
+            network_name: network_name.to_string(),
+            chain_id,
+            native_token: self.native_token(),
+            from: wallet.address(),
+            to: Some(token_address),
+            to_name: None,
+            to_kind: None,
+            amount: U256::zero(),
+            asset: AssetInfo {
+                symbol: self.native_token(),
+                decimals: 18,
+                contract: None,
+            },
+            call: Some(format!("approve({}, {})", address_book.describe(&spender), allowance)),
+            gas_limit: gas_limit_in_units,
+            fee_low: estimated_gas * expected_fee_per_gas,
+            fee_high: gas_limit_in_units * Self::fee_per_gas(&typed_tx),
+            warnings: checksum_warning(spender_input).into_iter().collect(),
+        };
+        if amount == U256::MAX {
+            println!(
+                "WARNING: an unlimited approval lets {} transfer all of your {}, now and in the future, until it is revoked.",
+                checksum(&spender), asset.symbol
+            );
+        }
+        summary.confirm(&address_book, skip_confirmation)?;
+        let request = SpendRequest {
+            recipient: None,
+            contract: Some(spender),
+            ..Self::transfer_request(&summary)
+        };
+        self.enforce_policy(&[request], Some(network_name))?;
+
+        let tx_hash = self.sign_and_broadcast(&typed_tx).await?;
+        if amount.is_zero() {
+            println!("Approval revoked. Hash: {:#x}", tx_hash);
+        } else {
+            println!("Approval sent. Hash: {:#x}", tx_hash);
+        }
+
+        self.save_history_to_file(&typed_tx, tx_hash, network_name).await;
+
+        Ok(format!("{:#x}", tx_hash))
+    }
+
+    pub async fn get_allowance(&self, token_address: &str, spender: &str) -> Result<U256, Box<dyn Error>> {
+        let token_address = parse_address(token_address, "token")?;
+        let spender = parse_address(spender, "spender")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+
+        let abi: Abi = serde_json::from_str(ERC20_ABI)?;
+        let contract = Contract::new(token_address, abi, provider.clone());
+        let allowance: U256 = contract
+            .method::<_, U256>("allowance", (wallet.address(), spender))?
+            .call()
+            .await?;
+
+        let asset = self.token_asset_info(token_address).await;
+        if allowance == U256::MAX {
+            println!("Allowance for {}: unlimited {}", self.address_book().describe(&spender), asset.symbol);
+        } else {
+            println!(
+                "Allowance for {}: {} {}",
+                self.address_book().describe(&spender),
+                format_units(allowance, asset.decimals)?,
+                asset.symbol
+            );
+        }
+        Ok(allowance)
     }
 
-    fn to_address(&self) -> Option<Address> {
-        self.to.as_deref().and_then(|to| Address::from_str(to).ok())
-    }
+    pub async fn nft_standard(&self, collection: Address) -> Result<NftStandard, Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let abi: Abi = serde_json::from_str(ERC165_ABI)?;
+        let contract = Contract::new(collection, abi, provider.clone());
+
+        for (interface_id, standard) in [
+            (ERC721_INTERFACE_ID, NftStandard::Erc721),
+            (ERC1155_INTERFACE_ID, NftStandard::Erc1155),
+        ] {
+            let supported = contract
+                .method::<_, bool>("supportsInterface", interface_id)?
+                .call()
+                .await
+                .unwrap_or(false);
+            if supported {
+                return Ok(standard);
+            }
+        }
+        Err(format!("{} does not report ERC-721 or ERC-1155 support via ERC-165", checksum(&collection)).into())
+    }
+
+    pub async fn nft_owner(&self, collection: &str, token_id: &str) -> Result<Address, Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let collection = parse_address(collection, "collection")?;
+        let token_id = U256::from_dec_str(token_id).map_err(|_| "Invalid token ID format")?;
+
+        if self.nft_standard(collection).await? != NftStandard::Erc721 {
+            return Err("ERC-1155 tokens have no single owner; use `nft balance --id` instead".into());
+        }
+        let abi: Abi = serde_json::from_str(ERC721_ABI)?;
+        let contract = Contract::new(collection, abi, provider.clone());
+        let owner: Address = contract.method::<_, Address>("ownerOf", token_id)?.call().await?;
+
+        println!("Owner of token {} in {}: {}", token_id, checksum(&collection), self.address_book().describe(&owner));
+        Ok(owner)
+    }
+
+    pub async fn nft_balance(&self, collection: &str, token_id: Option<&str>) -> Result<U256, Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let collection = parse_address(collection, "collection")?;
+
+        let standard = self.nft_standard(collection).await?;
+        let balance: U256 = match standard {
+            NftStandard::Erc721 => {
+                let abi: Abi = serde_json::from_str(ERC721_ABI)?;
+                let contract = Contract::new(collection, abi, provider.clone());
+                contract.method::<_, U256>("balanceOf", wallet.address())?.call().await?
+            }
+            NftStandard::Erc1155 => {
+                let token_id = token_id.ok_or("ERC-1155 balances require --id")?;
+                let token_id = U256::from_dec_str(token_id).map_err(|_| "Invalid token ID format")?;
+                let abi: Abi = serde_json::from_str(ERC1155_ABI)?;
+                let contract = Contract::new(collection, abi, provider.clone());
+                contract.method::<_, U256>("balanceOf", (wallet.address(), token_id))?.call().await?
+            }
+        };
+
+        match (standard, token_id) {
+            (NftStandard::Erc1155, Some(token_id)) => {
+                println!("{} balance of token {} in {}: {}", standard.name(), token_id, checksum(&collection), balance)
+            }
+            _ => println!("{} balance in {}: {}", standard.name(), checksum(&collection), balance),
+        }
+        Ok(balance)
+    }
+
+    pub async fn nft_uri(&self, collection: &str, token_id: &str) -> Result<String, Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let collection = parse_address(collection, "collection")?;
+        let token_id = U256::from_dec_str(token_id).map_err(|_| "Invalid token ID format")?;
+
+        let uri = match self.nft_standard(collection).await? {
+            NftStandard::Erc721 => {
+                let abi: Abi = serde_json::from_str(ERC721_ABI)?;
+                let contract = Contract::new(collection, abi, provider.clone());
+                contract.method::<_, String>("tokenURI", token_id)?.call().await?
+            }
+            NftStandard::Erc1155 => {
+                let abi: Abi = serde_json::from_str(ERC1155_ABI)?;
+                let contract = Contract::new(collection, abi, provider.clone());
+                let uri: String = contract.method::<_, String>("uri", token_id)?.call().await?;
+                NftService::expand_uri(&uri, token_id)
+            }
+        };
+
+        println!("Metadata URI of token {}: {}", token_id, uri);
+        Ok(uri)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn nft_transfer(
+        &mut self,
+        collection: &str,
+        to: &str,
+        token_id: &str,
+        amount: Option<&str>,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+        gas_limit: Option<&str>,
+        network_name: &str,
+        skip_confirmation: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let collection = parse_address(collection, "collection")?;
+        let (to_address, to_name) = self.resolve_destination(to).await?;
+        let token_id = U256::from_dec_str(token_id).map_err(|_| "Invalid token ID format")?;
+
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+
+        let standard = self.nft_standard(collection).await?;
+        let (mut typed_tx, amount) = match standard {
+            NftStandard::Erc721 => {
+                if amount.is_some_and(|amount| amount != "1") {
+                    return Err("ERC-721 tokens are transferred one at a time; omit --amount".into());
+                }
+                let abi: Abi = serde_json::from_str(ERC721_ABI)?;
+                let contract = Contract::new(collection, abi, provider.clone());
+                let tx = contract
+                    .method::<_, ()>("safeTransferFrom", (wallet.address(), to_address, token_id))?
+                    .from(wallet.address());
+                (tx.tx, U256::one())
+            }
+            NftStandard::Erc1155 => {
+                let amount = U256::from_dec_str(amount.unwrap_or("1")).map_err(|_| "Invalid amount format")?;
+                let abi: Abi = serde_json::from_str(ERC1155_ABI)?;
+                let contract = Contract::new(collection, abi, provider.clone());
+                let tx = contract
+                    .method::<_, ()>(
+                        "safeTransferFrom",
+                        (wallet.address(), to_address, token_id, amount, Bytes::default()),
+                    )?
+                    .from(wallet.address());
+                (tx.tx, amount)
+            }
+        };
+
+        let expected_fee_per_gas = self.apply_fees(&mut typed_tx, gas_price, speed).await?;
+        let (gas_limit_in_units, estimated_gas, chain_id) = self
+            .fill_transaction(&mut typed_tx, gas_limit, None, network_name)
+            .await?;
+
+        let summary = TxSummary {
+            network_name: network_name.to_string(),
+            chain_id,
+            native_token: self.native_token(),
+            from: wallet.address(),
+            to: Some(to_address),
+            to_name,
+            to_kind: None,
+            amount,
+            asset: AssetInfo {
+                symbol: format!("{} token #{}", standard.name(), token_id),
+                decimals: 0,
+                contract: Some(collection),
+            },
+            call: None,
+            gas_limit: gas_limit_in_units,
+            fee_low: estimated_gas * expected_fee_per_gas,
+            fee_high: gas_limit_in_units * Self::fee_per_gas(&typed_tx),
+            warnings: checksum_warning(to).into_iter().chain(recipient_warnings(to_address, wallet.address(), Some(collection))).collect(),
+        };
+        summary.confirm(&self.address_book(), skip_confirmation)?;
+        // NFTs are not covered by asset limits; only the recipient is checked.
+        let request = SpendRequest {
+            asset: None,
+            amount: U256::zero(),
+            ..Self::transfer_request(&summary)
+        };
+        self.enforce_policy(&[request], Some(network_name))?;
+
+        let tx_hash = self.sign_and_broadcast(&typed_tx).await?;
+        println!("NFT transfer sent. Hash: {:#x}", tx_hash);
+
+        self.save_history_to_file(&typed_tx, tx_hash, network_name).await;
+
+        Ok(format!("{:#x}", tx_hash))
+    }
+
+    pub async fn batch(
+        &mut self,
+        csv_path: &str,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+        network_name: &str,
+        skip_confirmation: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let rows = BatchService::read_rows(csv_path)?;
+        let progress_path = BatchService::progress_path(csv_path);
+        let mut progress = BatchProgress::load(&progress_path);
+        let account = checksum(&self.wallet.as_ref().ok_or("Wallet not set")?.address());
+        let chain_id = self.network.as_ref().ok_or("Network not set")?.chain_id;
+        progress.claim(&account, network_name, chain_id, &progress_path)?;
+        if !progress.sent.is_empty() {
+            self.reconcile_batch(&rows, &mut progress, &progress_path, network_name).await?;
+        }
+
+        let pending: Vec<&BatchRow> = rows
+            .iter()
+            .filter(|row| !progress.completed.contains_key(&row.key()))
+            .collect();
+        if pending.is_empty() {
+            println!("All {} rows of '{}' have already been sent.", rows.len(), csv_path);
+            return Ok(());
+        }
+
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let address_book = self.address_book();
+
+        println!("Batch payout from {}:", address_book.describe(&wallet.address()));
+        println!("  Network:      {}", network_name);
+        println!("  Rows:         {} ({} already sent, {} to send)", rows.len(), rows.len() - pending.len(), pending.len());
+        for (token, total) in BatchService::totals(&pending) {
+            let asset = match token {
+                Some(token) => self.token_asset_info(token).await,
+                None => AssetInfo {
+                    symbol: self.native_token(),
+                    decimals: 18,
+                    contract: None,
+                },
+            };
+            println!("  Total:        {} {}", format_units(total, asset.decimals)?, asset.symbol);
+        }
+        if !skip_confirmation {
+            confirm_prompt("Type 'yes' to sign and broadcast all transactions: ")?;
+        }
+
+        let mut nonce = self.next_nonce(network_name).await?;
+
+        for (index, row) in pending.iter().enumerate() {
+            let recipient = checksum(&row.recipient);
+            let amount = row.amount.to_string();
+            let token = row.token.as_ref().map(checksum);
+
+            let result = match self
+                .build_transfer(&recipient, &amount, token.as_deref(), gas_price, speed, None, Some(nonce), network_name)
+                .await
+            {
+                Ok((typed_tx, summary)) => match self.enforce_policy(&[Self::transfer_request(&summary)], Some(network_name)) {
+                    Ok(()) => self
+                        .send_batch_row(&typed_tx, row, &mut progress, &progress_path)
+                        .await
+                        .map(|tx_hash| (typed_tx, tx_hash)),
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok((typed_tx, tx_hash)) => {
+                    println!("[{}/{}] Line {}: sent to {}. Hash: {:#x}", index + 1, pending.len(), row.line, checksum(&row.recipient), tx_hash);
+                    self.save_history_to_file(&typed_tx, tx_hash, network_name).await;
+                    progress.sent.remove(&row.key());
+                    progress.completed.insert(row.key(), format!("{:#x}", tx_hash));
+                    progress.save(&progress_path)?;
+                    nonce += U256::one();
+                }
+                Err(e) => {
+                    println!("[{}/{}] Line {}: failed: {}", index + 1, pending.len(), row.line, e);
+                    return Err(format!(
+                        "Batch stopped after {} of {} transactions. Re-run `vaulty tx batch {}` to resume from line {}.",
+                        index,
+                        pending.len(),
+                        csv_path,
+                        row.line
+                    ).into());
+                }
+            }
+        }
+
+        println!("Batch complete: {} transactions sent. Progress saved to {}", pending.len(), progress_path);
+        Ok(())
+    }
+
+    /// Signs a batch row and records it as sent before broadcasting, so an interrupted broadcast is detected on
+    /// resume instead of being paid again.
+    async fn send_batch_row(
+        &self,
+        typed_tx: &TypedTransaction,
+        row: &BatchRow,
+        progress: &mut BatchProgress,
+        progress_path: &str,
+    ) -> Result<H256, Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let (signed_tx_bytes, tx_hash) = self.sign_raw(typed_tx).await?;
+
+        progress.sent.insert(row.key(), SentRow {
+            nonce: typed_tx.nonce().ok_or("Transaction has no nonce")?.as_u64(),
+            hash: format!("{:#x}", tx_hash),
+            raw_transaction: format!("{}", signed_tx_bytes),
+        });
+        progress.save(progress_path)?;
+
+        provider.send_raw_transaction(signed_tx_bytes).await?;
+        Ok(tx_hash)
+    }
+
+    /// Resolves rows recorded as sent by an interrupted run: rows whose transaction the node knows are completed,
+    /// rows whose nonce is still unused are sent again, and a nonce taken by another transaction stops the batch.
+    async fn reconcile_batch(
+        &self,
+        rows: &[BatchRow],
+        progress: &mut BatchProgress,
+        progress_path: &str,
+        network_name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let confirmed = provider.get_transaction_count(wallet.address(), None).await?;
+
+        for row in rows {
+            let Some(sent) = progress.sent.get(&row.key()).cloned() else {
+                continue;
+            };
+            let hash = H256::from_str(&sent.hash).map_err(|_| "Invalid transaction hash in batch progress")?;
+
+            if provider.get_transaction(hash).await?.is_some() {
+                println!("Line {}: already broadcast. Hash: {:#x}", row.line, hash);
+                if let Ok((typed_tx, _)) = OfflineService::decode_raw_transaction(&sent.raw_transaction) {
+                    self.save_history_to_file(&typed_tx, hash, network_name).await;
+                }
+                progress.sent.remove(&row.key());
+                progress.completed.insert(row.key(), sent.hash);
+            } else if U256::from(sent.nonce) < confirmed {
+                return Err(format!(
+                    "Line {} was signed with nonce {} as {:#x}, but that nonce was used by another transaction. \
+                    Check whether the payment was made and edit {} before resuming.",
+                    row.line, sent.nonce, hash, progress_path
+                ).into());
+            } else {
+                println!("Line {}: previous broadcast did not reach the network; it will be sent again.", row.line);
+                progress.sent.remove(&row.key());
+            }
+            progress.save(progress_path)?;
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn build_transfer(
+        &self,
+        to: &str,
+        value: &str,
+        token_address: Option<&str>,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+        gas_limit: Option<&str>,
+        nonce: Option<U256>,
+        network_name: &str,
+    ) -> Result<(TypedTransaction, TxSummary), Box<dyn Error>> {
+        let (to_address, to_name) = self.resolve_destination(to).await?;
+        let sweep = value == "max";
+        let mut value_in_wei = if sweep {
+            U256::zero()
+        } else {
+            U256::from_dec_str(value).map_err(|_| "Invalid amount format")?
+        };
+
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+
+        let (mut typed_tx, asset): (TypedTransaction, AssetInfo) = match token_address {
+            Some(token_address) => {
+                let token_address = parse_address(token_address, "token")?;
+                let abi: Abi = serde_json::from_str(ERC20_ABI)?;
+                let contract = Contract::new(token_address, abi, provider.clone());
+                if sweep {
+                    value_in_wei = contract.method::<_, U256>("balanceOf", wallet.address())?.call().await?;
+                    if value_in_wei.is_zero() {
+                        return Err("Token balance is zero; nothing to send".into());
+                    }
+                }
+                let tx = contract.method::<(Address, U256), bool>("transfer", (to_address, value_in_wei))?
+                    .from(wallet.address());
+                (tx.tx, self.token_asset_info(token_address).await)
+            }
+            None => {
+                let tx = TransactionRequest::pay(to_address, value_in_wei)
+                    .from(wallet.address());
+                let asset = AssetInfo {
+                    symbol: self.native_token(),
+                    decimals: 18,
+                    contract: None,
+                };
+                (tx.into(), asset)
+            }
+        };
+
+        let expected_fee_per_gas = self.apply_fees(&mut typed_tx, gas_price, speed).await?;
+        let (gas_limit_in_units, estimated_gas, chain_id) = self
+            .fill_transaction(&mut typed_tx, gas_limit, nonce, network_name)
+            .await?;
+
+        let fee_per_gas = Self::fee_per_gas(&typed_tx);
+        // OP Stack chains also charge an L1 data fee; a native sweep quotes it at the full balance.
+        let l1_fee;
+        if sweep && token_address.is_none() {
+            let balance = provider.get_balance(wallet.address(), None).await?;
+            l1_fee = self.l1_data_fee(&typed_tx, balance).await?;
+            let max_fee = gas_limit_in_units * fee_per_gas + l1_fee;
+            value_in_wei = balance
+                .checked_sub(max_fee)
+                .filter(|amount| !amount.is_zero())
+                .ok_or(format!(
+                    "Balance of {} {} does not cover the maximum fee of {} {}",
+                    format_units(balance, 18)?,
+                    self.native_token(),
+                    format_units(max_fee, 18)?,
+                    self.native_token()
+                ))?;
+            typed_tx.set_value(value_in_wei);
+        } else {
+            l1_fee = self.l1_data_fee(&typed_tx, typed_tx.value().copied().unwrap_or_default()).await?;
+        }
+
+        let token = asset.contract;
+        let to_kind = self.account_kind(to_address).await?;
+        let mut warnings: Vec<String> = checksum_warning(to)
+            .into_iter()
+            .chain(recipient_warnings(to_address, wallet.address(), token))
+            .collect();
+        // Sending a token to its own contract is already flagged above.
+        if token != Some(to_address) {
+            let symbol = token.map(|_| asset.symbol.as_str());
+            warnings.extend(to_kind.warning(&self.native_token(), symbol));
+        }
+        let summary = TxSummary {
+            network_name: network_name.to_string(),
+            chain_id,
+            native_token: self.native_token(),
+            from: wallet.address(),
+            to: Some(to_address),
+            to_name,
+            to_kind: Some(to_kind),
+            amount: value_in_wei,
+            asset,
+            call: None,
+            gas_limit: gas_limit_in_units,
+            fee_low: estimated_gas * expected_fee_per_gas + l1_fee,
+            fee_high: gas_limit_in_units * fee_per_gas + l1_fee,
+            warnings,
+        };
+
+        Ok((typed_tx, summary))
+    }
+
+    pub async fn replace(
+        &mut self,
+        tx_hash: &str,
+        cancel: bool,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+        network_name: &str,
+        skip_confirmation: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let hash: H256 = tx_hash.parse().map_err(|_| "Invalid transaction hash format")?;
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+
+        // The replacement pays at least the current fee for the speed, and always outbids the original.
+        let fees = self.choose_fee(gas_price, speed).await?;
+
+        let mut typed_tx: TypedTransaction = match provider.get_transaction(hash).await? {
+            Some(original) => {
+                if original.block_number.is_some() {
+                    return Err("Transaction is already mined and cannot be replaced".into());
+                }
+                if original.from != wallet.address() {
+                    return Err("Transaction was not sent from the logged-in account".into());
+                }
+
+                let (to, value, data, gas) = if cancel {
+                    (wallet.address(), U256::zero(), Bytes::default(), U256::from(21_000))
+                } else {
+                    let to = original.to.ok_or("Replacing contract creations is not supported")?;
+                    (to, original.value, original.input.clone(), original.gas)
+                };
+
+                if original.transaction_type == Some(U64::from(2)) {
+                    let max_priority_fee = original.max_priority_fee_per_gas.unwrap_or_default();
+                    let max_fee = original.max_fee_per_gas.unwrap_or_default();
+                    Eip1559TransactionRequest::new()
+                        .to(to)
+                        .value(value)
+                        .data(data)
+                        .gas(gas)
+                        .max_priority_fee_per_gas(NonceManager::replacement_fee(max_priority_fee, fees.max_priority_fee))
+                        .max_fee_per_gas(NonceManager::replacement_fee(max_fee, fees.max_fee))
+                        .from(wallet.address())
+                        .nonce(original.nonce)
+                        .into()
+                } else {
+                    let previous_gas_price = original.gas_price.unwrap_or_default();
+                    TransactionRequest::new()
+                        .to(to)
+                        .value(value)
+                        .data(data)
+                        .gas(gas)
+                        .gas_price(NonceManager::replacement_fee(previous_gas_price, fees.max_fee))
+                        .from(wallet.address())
+                        .nonce(original.nonce)
+                        .into()
+                }
+            }
+            None => {
+                if !cancel {
+                    return Err("Transaction not found by the provider. It may have been dropped; use `tx cancel` to free its nonce.".into());
+                }
+                let history = self.load_history_from_file(network_name)?;
+                let stored = history
+                    .iter()
+                    .find(|tx| tx.hash_matches(&hash))
+                    .ok_or("Transaction not found by the provider or in history")?;
+                let nonce = stored.nonce_as_u256().ok_or("Stored transaction has no nonce")?;
+                TransactionRequest::new()
+                    .to(wallet.address())
+                    .value(U256::zero())
+                    .gas(21_000)
+                    .gas_price(NonceManager::replacement_fee(stored.gas_price_as_u256(), fees.max_fee))
+                    .from(wallet.address())
+                    .nonce(nonce)
+                    .into()
+            }
+        };
+
+        let chain_id = provider.get_chainid().await?;
+        typed_tx.set_chain_id(chain_id.as_u64());
+
+        let gas_limit = typed_tx.gas().copied().unwrap_or_default();
+        let fee_per_gas = Self::fee_per_gas(&typed_tx);
+
+        let summary = TxSummary {
+            network_name: network_name.to_string(),
+            chain_id: chain_id.as_u64(),
+            native_token: self.native_token(),
+            from: wallet.address(),
+            to: typed_tx.to_addr().copied(),
+            to_name: None,
+            to_kind: None,
+            amount: typed_tx.value().copied().unwrap_or_default(),
+            asset: AssetInfo {
+                symbol: self.native_token(),
+                decimals: 18,
+                contract: None,
+            },
+            call: None,
+            gas_limit,
+            fee_low: gas_limit * fee_per_gas,
+            fee_high: gas_limit * fee_per_gas,
+            warnings: Vec::new(),
+        };
+        println!(
+            "Replacing transaction {:#x} (nonce {}) with a {} transaction.",
+            hash,
+            typed_tx.nonce().copied().unwrap_or_default(),
+            if cancel { "cancel" } else { "speed-up" }
+        );
+        summary.confirm(&self.address_book(), skip_confirmation)?;
+
+        let new_hash = self.sign_and_broadcast(&typed_tx).await?;
+        println!("Replacement transaction sent. Hash: {:#x}", new_hash);
+
+        self.save_history_to_file(&typed_tx, new_hash, network_name).await;
+        self.mark_replaced(&hash, network_name);
+
+        Ok(format!("{:#x}", new_hash))
+    }
+
+    /// `network_name` is the current network when the transaction is for its chain; its history backs the daily limits.
+    pub fn sign_offline(&self, params: &OfflineTxParams, network_name: Option<&str>) -> Result<String, Box<dyn Error>> {
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+
+        let typed_tx = OfflineService::build_transaction(params, wallet.address())?;
+        let requests = SpendRequest::from_transaction(&typed_tx, &self.native_token(), None);
+        self.enforce_policy(&requests, network_name)?;
+        let signed_tx_bytes = OfflineService::sign(&typed_tx, wallet)?;
+        let raw_tx = format!("{}", signed_tx_bytes);
+
+        println!("Transaction signed. Hash: {:#x}", H256::from(keccak256(&signed_tx_bytes)));
+        println!("Raw transaction:");
+        println!("{}", raw_tx);
+
+        Ok(raw_tx)
+    }
+
+    pub async fn broadcast(&mut self, raw_tx: &str, network_name: &str) -> Result<String, Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+
+        // Decoding validates the transaction and recovers the sender; the bytes relayed are exactly the ones given.
+        let (typed_tx, _) = OfflineService::decode_raw_transaction(raw_tx)?;
+        let signed_tx_bytes = Bytes::from_str(raw_tx.trim()).map_err(|_| "Invalid raw transaction hex")?;
+        let pending_tx: PendingTransaction<'_, Http> = provider.send_raw_transaction(signed_tx_bytes).await?;
+        let tx_hash = pending_tx.tx_hash();
+        println!("Transaction broadcast. Hash: {:#x}", tx_hash);
+
+        let own = self.wallet.as_ref().is_some_and(|wallet| typed_tx.from() == Some(&wallet.address()));
+        if own {
+            self.save_history_to_file(&typed_tx, tx_hash, network_name).await;
+        } else {
+            println!("Transaction is not from the logged-in account; it was not added to history.");
+        }
+
+        Ok(format!("{:#x}", tx_hash))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn prepare(
+        &self,
+        to: &str,
+        value: &str,
+        token_address: Option<&str>,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+        gas_limit: Option<&str>,
+        network_name: &str,
+        out: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let (typed_tx, summary) = self
+            .build_transfer(to, value, token_address, gas_price, speed, gas_limit, None, network_name)
+            .await?;
+        summary.print(&self.address_book())?;
+
+        let file = UnsignedTxFile::from_transaction(&typed_tx, &summary)?;
+        OfflineService::write_file(out, &file)?;
+        println!("Unsigned transaction written to {}", out);
+        Ok(())
+    }
+
+    pub fn sign_file(&self, file: &UnsignedTxFile, out: &str, skip_confirmation: bool) -> Result<String, Box<dyn Error>> {
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+
+        let typed_tx = OfflineService::build_transaction(&file.params(), wallet.address())?;
+        let summary = file.summary(&typed_tx)?;
+        if summary.from != wallet.address() {
+            return Err(format!(
+                "Transaction was prepared for {}, but the logged-in account is {}",
+                checksum(&summary.from),
+                checksum(&wallet.address())
+            ).into());
+        }
+        summary.confirm(&self.address_book(), skip_confirmation)?;
+        let token = summary.asset.contract.map(|_| (summary.asset.symbol.clone(), summary.asset.decimals));
+        let requests = SpendRequest::from_transaction(&typed_tx, &file.network.native_token, token);
+        self.enforce_policy(&requests, Some(&file.network.name))?;
+
+        let signed_tx_bytes = OfflineService::sign(&typed_tx, wallet)?;
+        let tx_hash = H256::from(keccak256(&signed_tx_bytes));
+
+        let signed = SignedTxFile {
+            version: TX_FILE_VERSION,
+            network: TxFileNetwork {
+                name: file.network.name.clone(),
+                chain_id: file.network.chain_id,
+                native_token: file.network.native_token.clone(),
+            },
+            hash: format!("{:#x}", tx_hash),
+            raw_transaction: format!("{}", signed_tx_bytes),
+        };
+        OfflineService::write_file(out, &signed)?;
+        println!("Transaction signed. Hash: {:#x}", tx_hash);
+        println!("Signed transaction written to {}", out);
+
+        Ok(format!("{:#x}", tx_hash))
+    }
+
+    pub async fn broadcast_file(&mut self, file: &SignedTxFile, network_name: &str) -> Result<String, Box<dyn Error>> {
+        let (typed_tx, _) = OfflineService::decode_raw_transaction(&file.raw_transaction)?;
+        if typed_tx.chain_id().map(|id| id.as_u64()) != Some(file.network.chain_id) {
+            return Err("Signed transaction chain ID does not match the network in the file".into());
+        }
+        self.broadcast(&file.raw_transaction, network_name).await
+    }
+
+    async fn next_nonce(&self, network_name: &str) -> Result<U256, Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+
+        let confirmed = provider.get_transaction_count(wallet.address(), None).await?;
+        let pending = provider
+            .get_transaction_count(wallet.address(), Some(BlockNumber::Pending.into()))
+            .await?;
+        // Recorded nonces at or above the node's pending count are only skipped while the node still knows the
+        // transaction; one that was dropped from the mempool leaves its nonce free for reuse.
+        let mut known = Vec::new();
+        for tx in self.load_history_from_file(network_name)? {
+            if tx.sender_address() != Some(wallet.address()) {
+                continue;
+            }
+            let (Some(nonce), Some(hash)) = (tx.nonce_as_u256(), tx.hash_as_h256()) else {
+                continue;
+            };
+            if nonce >= pending && provider.get_transaction(hash).await?.is_some() {
+                known.push(nonce);
+            }
+        }
+
+        Ok(NonceManager::next_nonce(confirmed, pending, &known))
+    }
+
+    async fn fill_transaction(
+        &self,
+        typed_tx: &mut TypedTransaction,
+        gas_limit: Option<&str>,
+        nonce: Option<U256>,
+        network_name: &str,
+    ) -> Result<(U256, U256, u64), Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+
+        let (gas_limit_in_units, estimated_gas) = self.resolve_gas_limit(typed_tx, gas_limit).await?;
+
+        let nonce = match nonce {
+            Some(nonce) => nonce,
+            None => self.next_nonce(network_name).await?,
+        };
+
+        let chain_id = provider.get_chainid().await?;
+        typed_tx.set_chain_id(chain_id.as_u64());
+        typed_tx.set_gas(gas_limit_in_units);
+        typed_tx.set_nonce(nonce);
+
+        Ok((gas_limit_in_units, estimated_gas, chain_id.as_u64()))
+    }
+
+    async fn fee_estimates(&self) -> Result<(Vec<FeeEstimate>, Option<bool>), Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let history = provider
+            .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &FEE_REWARD_PERCENTILES)
+            .await
+            .ok();
+
+        if let Some(history) = &history {
+            let estimates: Option<Vec<FeeEstimate>> =
+                FeeSpeed::ALL.iter().map(|speed| FeeService::estimate(history, *speed)).collect();
+            if let Some(estimates) = estimates {
+                return Ok((estimates, FeeService::base_fee_rising(history)));
+            }
+        }
+
+        let gas_price = provider.get_gas_price().await?;
+        let estimates = FeeSpeed::ALL.iter().map(|speed| FeeService::legacy_estimate(gas_price, *speed)).collect();
+        Ok((estimates, None))
+    }
+
+    async fn fee_estimate(&self, speed: FeeSpeed) -> Result<FeeEstimate, Box<dyn Error>> {
+        let (estimates, _) = self.fee_estimates().await?;
+        estimates
+            .into_iter()
+            .find(|estimate| estimate.speed == speed)
+            .ok_or_else(|| format!("No {} fee estimate available", speed).into())
+    }
+
+    /// Fee of a new transaction: an explicit gas price is used as is, otherwise the estimator's preset for the speed.
+    async fn choose_fee(&self, gas_price: Option<&str>, speed: Option<FeeSpeed>) -> Result<FeeEstimate, Box<dyn Error>> {
+        match gas_price {
+            Some(gp) => {
+                let gas_price = U256::from_dec_str(gp).map_err(|_| "Invalid gas price format")?;
+                Ok(FeeEstimate {
+                    speed: speed.unwrap_or(FeeSpeed::Normal),
+                    base_fee: U256::zero(),
+                    max_priority_fee: gas_price,
+                    max_fee: gas_price,
+                })
+            }
+            None => self.fee_estimate(speed.unwrap_or(FeeSpeed::Normal)).await,
+        }
+    }
+
+    /// Sets the fee fields of a built transaction, shared by every write path; returns the fee per gas expected to
+    /// be paid, for the low end of the confirmation fee range.
+    async fn apply_fees(
+        &self,
+        typed_tx: &mut TypedTransaction,
+        gas_price: Option<&str>,
+        speed: Option<FeeSpeed>,
+    ) -> Result<U256, Box<dyn Error>> {
+        let fees = self.choose_fee(gas_price, speed).await?;
+        *typed_tx = FeeService::apply(typed_tx, &fees);
+        Ok(fees.expected_fee_per_gas())
+    }
+
+    pub async fn gas_options(&self, network_name: &str) -> Result<(), Box<dyn Error>> {
+        let (estimates, rising) = self.fee_estimates().await?;
+        let native_token = self.native_token();
+
+        match (estimates.first(), rising) {
+            (Some(estimate), Some(rising)) => println!(
+                "Gas fees on '{}' (next base fee {} gwei, {}):",
+                network_name,
+                format_units(estimate.base_fee, 9)?,
+                if rising { "rising" } else { "steady or falling" }
+            ),
+            _ => println!("Gas fees on '{}' (no base fee; legacy gas price):", network_name),
+        }
+        for estimate in &estimates {
+            println!("  {}:", estimate.speed);
+            if estimate.is_legacy() {
+                println!("    Gas price:       {} gwei", format_units(estimate.max_fee, 9)?);
+            } else {
+                println!("    Max fee:         {} gwei", format_units(estimate.max_fee, 9)?);
+                println!("    Priority fee:    {} gwei", format_units(estimate.max_priority_fee, 9)?);
+            }
+            for (label, gas) in [("Transfer", NATIVE_TRANSFER_GAS), ("ERC-20 transfer", ERC20_TRANSFER_GAS)] {
+                let gas = U256::from(gas);
+                println!(
+                    "    {:<17}~{} {} (max {} {})",
+                    format!("{}:", label),
+                    format_units(gas * estimate.expected_fee_per_gas(), 18)?,
+                    native_token,
+                    format_units(gas * estimate.max_fee, 18)?,
+                    native_token
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn fee_per_gas(tx: &TypedTransaction) -> U256 {
+        match tx {
+            TypedTransaction::Eip1559(inner) => inner.max_fee_per_gas.unwrap_or_default(),
+            _ => tx.gas_price().unwrap_or_default(),
+        }
+    }
+
+    /// OP Stack chains charge a separate L1 data fee on top of the L2 gas fee. It is quoted by the
+    /// GasPriceOracle predeploy for the signed transaction, with a margin for L1 base fee changes.
+    async fn l1_data_fee(&self, tx: &TypedTransaction, value: U256) -> Result<U256, Box<dyn Error>> {
+        let chain_id = tx.chain_id().map(|id| id.as_u64()).unwrap_or_default();
+        if !OP_STACK_CHAIN_IDS.contains(&chain_id) {
+            return Ok(U256::zero());
+        }
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+
+        let mut quoted_tx = tx.clone();
+        quoted_tx.set_value(value);
+        let signature = wallet.sign_transaction(&quoted_tx).await?;
+        let signed_tx_bytes = quoted_tx.rlp_signed(&signature);
+
+        let oracle = Address::from_str(OP_GAS_PRICE_ORACLE)?;
+        let abi = parse_abi(&["function getL1Fee(bytes) view returns (uint256)"])?;
+        let contract = Contract::new(oracle, abi, provider.clone());
+        let l1_fee: U256 = contract
+            .method::<_, U256>("getL1Fee", signed_tx_bytes)?
+            .call()
+            .await?;
+
+        Ok(l1_fee * 5 / 4)
+    }
+
+    async fn resolve_gas_limit(
+        &self,
+        tx: &TypedTransaction,
+        gas_limit: Option<&str>,
+    ) -> Result<(U256, U256), Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        match gas_limit {
+            Some(gl) => {
+                let gas_limit = U256::from_dec_str(gl).map_err(|_| "Invalid gas limit format")?;
+                let estimated = provider.estimate_gas(tx, None).await.unwrap_or(gas_limit);
+                Ok((gas_limit, estimated.min(gas_limit)))
+            }
+            None => {
+                let estimated = provider.estimate_gas(tx, None).await?;
+                Ok((estimated, estimated))
+            }
+        }
+    }
+
+    fn transfer_request(summary: &TxSummary) -> SpendRequest {
+        SpendRequest {
+            recipient: summary.to,
+            contract: None,
+            asset: summary.asset.contract,
+            amount: summary.amount,
+            decimals: Some(summary.asset.decimals),
+            symbol: summary.asset.symbol.clone(),
+        }
+    }
+
+    /// Checks the account's spending policy before signing. Violations are listed and can only be overridden by
+    /// re-entering the account password. Without a network there is no history, so daily limits count as broken.
+    fn enforce_policy(&self, requests: &[SpendRequest], network_name: Option<&str>) -> Result<(), Box<dyn Error>> {
+        let account_name = Self::load_account_name().unwrap_or_default();
+        let Some(policy) = SpendingPolicy::load(&account_name)? else {
+            return Ok(());
+        };
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let history = match network_name {
+            Some(network_name) => Some(self.load_history_from_file(network_name)?),
+            None => None,
+        };
+        let address_book = self.address_book();
+
+        let mut violations = Vec::new();
+        for request in requests {
+            let spent = history.as_ref().map(|history| {
+                SpendingPolicy::spent_since(
+                    history,
+                    wallet.address(),
+                    request.asset,
+                    HistoryService::now().saturating_sub(POLICY_WINDOW_SECONDS),
+                )
+            });
+            violations.extend(policy.violations(request, spent, &address_book)?);
+        }
+        if violations.is_empty() {
+            return Ok(());
+        }
 
-    fn gas_as_u256(&self) -> U256 {
-        U256::from_str_radix(&self.gas.trim_start_matches("0x"), 16).unwrap_or_default()
+        println!("This transaction breaks the spending policy of account '{}':", account_name);
+        for violation in &violations {
+            println!("  - {}", violation);
+        }
+        let password = AccountService::get_password("Re-enter the account password to override (leave empty to cancel): ");
+        if password.is_empty() || !AccountService::verify_password(&account_name, &password)? {
+            return Err("Transaction blocked by the spending policy".into());
+        }
+        println!("Spending policy overridden.");
+        Ok(())
     }
 
-    fn gas_price_as_u256(&self) -> U256 {
-        U256::from_str_radix(&self.gas_price.trim_start_matches("0x"), 16).unwrap_or_default()
+    async fn sign_and_broadcast(&self, typed_tx: &TypedTransaction) -> Result<H256, Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let (signed_tx_bytes, _) = self.sign_raw(typed_tx).await?;
+        let pending_tx: PendingTransaction<'_, Http> = provider.send_raw_transaction(signed_tx_bytes).await?;
+        Ok(pending_tx.tx_hash())
     }
 
-    fn value_as_u256(&self) -> U256 {
-        U256::from_str_radix(&self.value.trim_start_matches("0x"), 16).unwrap_or_default()
+    async fn sign_raw(&self, typed_tx: &TypedTransaction) -> Result<(Bytes, H256), Box<dyn Error>> {
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let signature: Signature = wallet.sign_transaction(typed_tx).await?;
+        let signed_tx_bytes = typed_tx.rlp_signed(&signature);
+        let tx_hash = H256::from(keccak256(&signed_tx_bytes));
+        Ok((signed_tx_bytes, tx_hash))
     }
-}
 
-impl StoredTransaction {
-    fn from_typed_transaction(tx: &TypedTransaction) -> Self {
-        let tx_type = match tx {
-            TypedTransaction::Legacy(_) => "Legacy",
-            TypedTransaction::Eip2930(_) => "Eip2930",
-            TypedTransaction::Eip1559(_) => "Eip1559",
-            _ => "Unknown",
+    async fn token_asset_info(&self, token_address: Address) -> AssetInfo {
+        match self.token_metadata(token_address).await {
+            Some((symbol, decimals)) => AssetInfo {
+                symbol,
+                decimals,
+                contract: Some(token_address),
+            },
+            None => AssetInfo {
+                symbol: checksum(&token_address),
+                decimals: 0,
+                contract: Some(token_address),
+            },
         }
-            .to_string();
-
-        let from = tx.from().map(|addr| format!("{:?}", addr)).unwrap_or_default();
+    }
 
-        let (to, token_value) = if let Some(input_data) = tx.data() {
-            if input_data.len() >= 68 && input_data.starts_with(&[0xa9, 0x05, 0x9c, 0xbb]) {
-                let recipient_address = Address::from_slice(&input_data[16..36]);
-                let token_amount = U256::from_big_endian(&input_data[36..68]);
-                (Some(format!("{:?}", recipient_address)), Some(token_amount.to_string()))
-            } else {
-                (tx.to().map(|to| format!("{:?}", to)), None)
-            }
-        } else {
-            (tx.to().map(|to| format!("{:?}", to)), None)
-        };
+    /// ERC-20 symbol and decimals; none when the address does not answer both, or reports more decimals than an amount
+    /// can be scaled by.
+    async fn token_metadata(&self, token_address: Address) -> Option<(String, u32)> {
+        let provider = self.provider.as_ref()?;
+        let abi = serde_json::from_str::<Abi>(ERC20_ABI).ok()?;
 
-        StoredTransaction {
-            tx_type,
-            from,
-            to,
-            gas: tx.gas().map(|g| format!("{:#x}", g)).unwrap_or_default(),
-            gas_price: tx.gas_price().map(|gp| format!("{:#x}", gp)).unwrap_or_default(),
-            value: tx.value().map(|v| format!("{:#x}", v)).unwrap_or("0x0".to_string()),
-            token_value,
-        }
+        let contract = Contract::new(token_address, abi, provider.clone());
+        let symbol = contract.method::<_, String>("symbol", ()).ok()?.call().await.ok()?;
+        let decimals = contract.method::<_, u8>("decimals", ()).ok()?.call().await.ok()?;
+        Some((symbol, decimals as u32)).filter(|(_, decimals)| *decimals <= MAX_DECIMALS)
     }
-}
-
-pub struct TransactionService {
-    pub provider: Option<Arc<Provider<Http>>>,
-    pub wallet: Option<LocalWallet>,
-}
 
-impl TransactionService {
-    pub fn new() -> Self {
-        TransactionService {
-            provider: None,
-            wallet: None,
-        }
+    /// Classifies an address by its code; contracts answering ERC-20 `symbol` and `decimals` are token contracts.
+    async fn account_kind(&self, address: Address) -> Result<AccountKind, Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let code = provider.get_code(address, None).await?;
+        Ok(match AccountKind::from_code(&code) {
+            AccountKind::Contract => match self.token_metadata(address).await {
+                Some((symbol, _)) => AccountKind::Token(symbol),
+                None => AccountKind::Contract,
+            },
+            kind => kind,
+        })
     }
 
-    pub fn set_provider(&mut self, provider_url: &str) {
-        let provider = Provider::<Http>::try_from(provider_url)
-            .expect("Invalid provider URL");
-        self.provider = Some(Arc::new(provider));
+    fn native_token(&self) -> String {
+        self.network
+            .as_ref()
+            .map(|network| network.native_token.clone())
+            .unwrap_or_else(|| "ETH".to_string())
     }
 
-    pub fn set_wallet(&mut self, wallet: LocalWallet) {
-        self.wallet = Some(wallet);
+    fn address_book(&self) -> AddressBook {
+        AddressBook::load(&Self::load_account_name().unwrap_or_default())
     }
 
-    pub async fn send(
-        &mut self,
-        to: &str,
-        value: &str,
-        gas_price: Option<&str>,
-        gas_limit: Option<&str>,
-        network_name: &str,
-    ) -> Result<String, Box<dyn Error>> {
-        let to_address = Address::from_str(to).map_err(|_| "Invalid destination address format")?;
-        let value_in_wei = U256::from_dec_str(value).map_err(|_| "Invalid amount format")?;
+    fn ens_registry(&self) -> Option<Address> {
+        self.network.as_ref().and_then(EnsService::registry)
+    }
 
+    /// Parses a destination given as an address or, on networks with an ENS registry, as an ENS name.
+    async fn resolve_destination(&self, to: &str) -> Result<(Address, Option<String>), Box<dyn Error>> {
+        if !EnsService::is_name(to) {
+            return Ok((parse_address(to, "destination")?, None));
+        }
+        let registry = self
+            .ens_registry()
+            .ok_or(format!("Cannot resolve '{}': ENS is not configured on this network (see `network set-ens`)", to))?;
         let provider = self.provider.as_ref().ok_or("Provider not set")?;
-        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
-
-        let gas_price_in_wei = match gas_price {
-            Some(gp) => U256::from_dec_str(gp).map_err(|_| "Invalid gas price format")?,
-            None => provider.get_gas_price().await?,
-        };
+        let address = EnsService::resolve(provider, registry, to).await?;
+        Ok((address, Some(EnsService::normalize(to))))
+    }
 
-        let mut tx = TransactionRequest::pay(to_address, value_in_wei)
-            .from(wallet.address())
-            .gas_price(gas_price_in_wei);
+    /// Reverse-resolved ENS names; empty when the network has no ENS registry.
+    async fn ens_names(&self, addresses: impl IntoIterator<Item = Address>) -> HashMap<Address, String> {
+        match (self.provider.as_ref(), self.ens_registry()) {
+            (Some(provider), Some(registry)) => EnsService::lookup_all(provider, registry, addresses).await,
+            _ => HashMap::new(),
+        }
+    }
 
-        let mut typed_tx: TypedTransaction = tx.clone().into();
+    pub async fn history(&self, network_name: &str, query: HistoryQuery, compact: bool) -> Result<(), Box<dyn Error>> {
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let mut history = self.load_history_from_file(network_name)?;
+        let account_name = Self::load_account_name().unwrap_or_default();
 
-        let gas_limit_in_units = match gas_limit {
-            Some(gl) => U256::from_dec_str(gl).map_err(|_| "Invalid gas limit format")?,
-            None => provider.estimate_gas(&typed_tx, None).await?,
-        };
+        if history.is_empty() {
+            println!("No transaction history found for account {} on network {}", account_name, network_name);
+            return Ok(());
+        }
 
-        let nonce = provider
-            .get_transaction_count(wallet.address(), None)
-            .await?;
+        match self.refresh_statuses(&mut history).await {
+            Ok(true) => self.write_history(&history, network_name),
+            Ok(false) => {}
+            Err(e) => println!("Could not refresh transaction statuses: {}", e),
+        }
 
-        let chain_id = provider.get_chainid().await?;
-        typed_tx.set_chain_id(chain_id.as_u64());
-        typed_tx.set_gas(gas_limit_in_units);
-        typed_tx.set_nonce(nonce);
+        let native_token = self.native_token();
+        let address_book = self.address_book();
+        let query = self.resolve_counterparty_label(query, &address_book);
+        let offset = query.offset;
+        let (history, total) = query.select(history, wallet.address(), |tx| (tx, native_token.as_str()))?;
 
-        let signature: Signature = wallet.sign_transaction(&typed_tx).await?;
-        let signed_tx_bytes = typed_tx.rlp_signed(&signature);
-        let pending_tx: PendingTransaction<'_, Http> = provider.send_raw_transaction(signed_tx_bytes).await?;
-        let tx_hash = pending_tx.tx_hash();
-        println!("Transaction sent. Hash: {:#x}", tx_hash);
+        if history.is_empty() {
+            println!("No transactions match the given filters ({} matching, offset {})", total, offset);
+            return Ok(());
+        }
+        let ens_names = self
+            .ens_names(history.iter().flat_map(|tx| [tx.sender_address(), tx.to_address()]).flatten())
+            .await;
+        println!(
+            "Transaction history for account '{}' on network '{}' ({}-{} of {} matching):",
+            account_name,
+            network_name,
+            offset + 1,
+            offset + history.len(),
+            total
+        );
 
-        self.save_history_to_file(&typed_tx, network_name);
+        if compact {
+            println!(
+                "{:<4}  {:<19}  {:<8}  {:<4}  {:>24} {:<10}  {:<42}  Hash",
+                "#", "Date (UTC)", "Status", "Dir", "Amount", "Asset", "Counterparty"
+            );
+            for (index, tx) in history.iter().enumerate() {
+                let (asset, _, amount) = tx.asset_amount(&native_token);
+                let counterparty = tx
+                    .counterparty(wallet.address())
+                    .map(|address| match (address_book.label(&address), ens_names.get(&address)) {
+                        (Some(label), _) => label.to_string(),
+                        (None, Some(name)) => name.clone(),
+                        (None, None) => checksum(&address),
+                    })
+                    .unwrap_or_default();
+                println!(
+                    "{:<4}  {:<19}  {:<8}  {:<4}  {:>24} {:<10}  {:<42}  {}",
+                    offset + index + 1,
+                    tx.timestamp.map(HistoryService::format_timestamp).unwrap_or_default(),
+                    tx.status.name(),
+                    tx.direction(wallet.address()),
+                    amount,
+                    asset,
+                    counterparty,
+                    tx.hash.as_deref().unwrap_or_default()
+                );
+            }
+            return Ok(());
+        }
 
-        Ok(format!("{:#x}", tx_hash))
+        for (index, tx) in history.iter().enumerate() {
+            println!("Transaction {}:", offset + index + 1);
+            println!("  Hash: {}", tx.hash.as_deref().unwrap_or("not recorded"));
+            println!("  Status: {}", tx.status.name());
+            if let Some(timestamp) = tx.timestamp {
+                println!("  Date: {} UTC", HistoryService::format_timestamp(timestamp));
+            }
+            if let Some(kind) = &tx.kind {
+                println!("  Type: {}", kind);
+            }
+            if let Some(nonce) = tx.nonce_as_u256() {
+                println!("  Nonce: {}", nonce);
+            }
+            if let Some(chain_id) = tx.chain_id {
+                println!("  Chain ID: {}", chain_id);
+            }
+            let from = tx.sender_address().unwrap_or(Address::zero());
+            println!("  From: {}", address_book.describe_with_name(&from, ens_names.get(&from).map(String::as_str)));
+            match tx.to_address() {
+                Some(to) => println!("  To: {}", address_book.describe_with_name(&to, ens_names.get(&to).map(String::as_str))),
+                None => println!("  To: (contract creation)"),
+            }
+            if let Some(contract_address) = &tx.contract_address {
+                println!("  Contract Created: {}", checksum_str(contract_address));
+            }
+            if let (Some(collection), Some(token_id)) = (&tx.collection, &tx.token_id) {
+                println!("  Collection: {}", checksum_str(collection));
+                println!("  Token ID: {}", token_id);
+            }
+            println!("  Value: {} {}", format_units(tx.value_as_u256(), 18)?, native_token);
+            if let (Some(token), Some(token_value)) = (&tx.token_contract, tx.token_value_as_u256()) {
+                let symbol = tx.token_symbol.clone().unwrap_or_else(|| token.clone());
+                let amount = match tx.known_token_decimals() {
+                    Some(decimals) => format_units(token_value, decimals)?,
+                    None => token_value.to_string(),
+                };
+                println!("  Token Amount: {} {} ({})", amount, symbol, checksum_str(token));
+            } else if let Some(token_value) = tx.token_value_as_u256() {
+                println!("  Token Amount: {}", token_value);
+            }
+            println!("  Gas Price: {} gwei", format_units(tx.gas_price_as_u256(), 9)?);
+            println!("  Gas Limit: {}", tx.gas_as_u256());
+            println!("--------------------------------");
+        }
+        Ok(())
     }
 
-    pub async fn send_token(
-        &mut self,
-        to: &str,
-        value: &str,
-        token_address: &str,
-        gas_price: Option<&str>,
-        gas_limit: Option<&str>,
-        network_name: &str,
-    ) -> Result<String, Box<dyn Error>> {
-        let to_address = Address::from_str(to).map_err(|_| "Invalid destination address format")?;
-        let value_in_wei = U256::from_dec_str(value).map_err(|_| "Invalid amount format")?;
-
-        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+    /// Writes history for the given networks (key and info) as CSV or JSON, to `out` or stdout. Statuses and fees
+    /// are refreshed first for the current network, the only one with a provider.
+    pub async fn export_history(
+        &self,
+        networks: &[(String, NetworkInfo)],
+        current_network: &str,
+        query: HistoryQuery,
+        format: &str,
+        out: Option<&str>,
+    ) -> Result<(), Box<dyn Error>> {
         let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let mut entries = Vec::new();
 
-        let gas_price_in_wei = match gas_price {
-            Some(gp) => U256::from_dec_str(gp).map_err(|_| "Invalid gas price format")?,
-            None => provider.get_gas_price().await?,
-        };
-
-        let token_address = Address::from_str(token_address).map_err(|_| "Invalid token address format")?;
-        let abi: Abi = serde_json::from_str(ERC20_ABI)?;
-        let contract = Contract::new(token_address, abi, provider.clone());
-        let tx = contract.method::<(Address, U256), bool>("transfer", (to_address, value_in_wei))?
-            .from(wallet.address())
-            .gas_price(gas_price_in_wei);
+        for (network_name, network) in networks {
+            let mut history = self.load_network_history(network_name, Some(network.chain_id))?;
+            if network_name == current_network {
+                match self.refresh_statuses(&mut history).await {
+                    Ok(true) => self.write_history(&history, network_name),
+                    Ok(false) => {}
+                    Err(e) => println!("Could not refresh transaction statuses: {}", e),
+                }
+            }
+            entries.extend(history.into_iter().map(|tx| (network_name, network, tx)));
+        }
 
-        let mut tx_request = tx.tx;
+        let query = self.resolve_counterparty_label(query, &self.address_book());
+        let (entries, _) = query.select(entries, wallet.address(), |(_, network, tx)| (tx, network.native_token.as_str()))?;
+        let mut records: Vec<HistoryRecord> = entries
+            .iter()
+            .map(|(network_name, network, tx)| HistoryRecord::new(tx, network, network_name, wallet.address()))
+            .collect();
+        HistoryService::attribute_fees(&mut records);
 
-        let gas_limit_in_units = match gas_limit {
-            Some(gl) => U256::from_dec_str(gl).map_err(|_| "Invalid gas limit format")?,
-            None => provider.estimate_gas(&tx_request, None).await?,
+        let output = match format {
+            "csv" => HistoryService::to_csv(&records),
+            "json" => serde_json::to_string_pretty(&records)?,
+            _ => return Err(format!("Unsupported export format '{}' (expected csv or json)", format).into()),
         };
 
-        let nonce = provider
-            .get_transaction_count(wallet.address(), None)
-            .await?;
+        match out {
+            Some(out) => {
+                fs::write(out, output)?;
+                println!("Exported {} transactions to {}", records.len(), out);
+            }
+            None => print!("{}", output),
+        }
+        Ok(())
+    }
 
-        let chain_id = provider.get_chainid().await?;
-        tx_request.set_chain_id(chain_id.as_u64());
-        tx_request.set_gas(gas_limit_in_units);
-        tx_request.set_nonce(nonce);
+    fn resolve_counterparty_label(&self, mut query: HistoryQuery, address_book: &AddressBook) -> HistoryQuery {
+        if let Some(address) = query.counterparty.as_deref().and_then(|label| address_book.resolve(label)) {
+            query.counterparty = Some(format!("{:?}", address));
+        }
+        query
+    }
 
-        let typed_tx: TypedTransaction = tx_request.into();
+    pub async fn sync(
+        &self,
+        network_name: &str,
+        from_block: Option<u64>,
+        chunk_size: Option<u64>,
+    ) -> Result<(), Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let account = wallet.address();
+        let account_topic = SyncService::address_topic(account);
+        let chain_id = provider.get_chainid().await?.as_u64();
+        let latest = provider.get_block_number().await?.as_u64();
 
-        let signature = wallet.sign_transaction(&typed_tx).await?;
-        let signed_tx_bytes = typed_tx.rlp_signed(&signature);
-        let pending_tx: PendingTransaction<'_, Http> = provider.send_raw_transaction(signed_tx_bytes).await?;
+        let checkpoint_path = SyncCheckpoint::path(&self.tx_history_file(network_name));
+        let mut checkpoint = SyncCheckpoint::load(&checkpoint_path)?;
+        let start = match (from_block, checkpoint.last_block) {
+            (Some(block), _) => block,
+            (None, Some(last_block)) => last_block + 1,
+            (None, None) => {
+                println!(
+                    "No sync checkpoint found; scanning the last {} blocks. Use --from-block to scan further back.",
+                    SYNC_DEFAULT_LOOKBACK
+                );
+                latest.saturating_sub(SYNC_DEFAULT_LOOKBACK)
+            }
+        };
+        if start > latest {
+            println!("History is already synced up to block {}", latest);
+            return Ok(());
+        }
+        println!("Syncing transfers for {} on '{}' from block {} to {}...", checksum(&account), network_name, start, latest);
 
-        let tx_hash = pending_tx.tx_hash();
-        println!("Token transfer sent. Hash: {:#x}", tx_hash);
+        let mut history = self.load_history_from_file(network_name)?;
+        let mut ranges: VecDeque<(u64, u64)> =
+            SyncService::chunk_ranges(start, latest, chunk_size.unwrap_or(SYNC_CHUNK_SIZE)).into();
+        let mut traces_supported = true;
+        let mut assets: HashMap<Address, AssetInfo> = HashMap::new();
+        let mut block_times: HashMap<u64, u64> = HashMap::new();
+        let mut added = 0;
 
-        self.save_history_to_file(&typed_tx, network_name);
+        while let Some((from, to)) = ranges.pop_front() {
+            let filter = Filter::new().from_block(from).to_block(to).topic0(SyncService::transfer_topic());
+            let logs = match (
+                provider.get_logs(&filter.clone().topic2(account_topic)).await,
+                provider.get_logs(&filter.topic1(account_topic)).await,
+            ) {
+                (Ok(mut incoming), Ok(outgoing)) => {
+                    incoming.extend(outgoing);
+                    incoming
+                }
+                (Err(e), _) | (_, Err(e)) if from == to => return Err(e.into()),
+                _ => {
+                    // Providers cap the range or result size of eth_getLogs; retry with smaller ranges.
+                    let middle = from + (to - from) / 2;
+                    ranges.push_front((middle + 1, to));
+                    ranges.push_front((from, middle));
+                    continue;
+                }
+            };
 
-        Ok(format!("{:#x}", tx_hash))
-    }
+            let mut entries: Vec<(u64, StoredTransaction)> = logs
+                .iter()
+                .filter_map(|log| {
+                    let block = log.block_number?.as_u64();
+                    SyncService::transfer_from_log(log, chain_id).map(|entry| (block, entry))
+                })
+                .collect();
 
-    pub fn history(&self, network_name: &str) {
-        let history = self.load_history_from_file(network_name);
-        let account_name = Self::load_account_name().unwrap_or_default();
+            if traces_supported {
+                let trace_filter = TraceFilter::default().from_block(from).to_block(to).to_address(vec![account]);
+                match provider.trace_filter(trace_filter).await {
+                    Ok(traces) => entries.extend(traces.iter().filter_map(|trace| {
+                        SyncService::transfer_from_trace(trace, chain_id).map(|entry| (trace.block_number, entry))
+                    })),
+                    Err(e) => {
+                        traces_supported = false;
+                        println!("Skipping incoming native transfers: the RPC does not support trace_filter ({})", e);
+                    }
+                }
+            }
 
-        if history.is_empty() {
-            println!("No transaction history found for account {} on network {}", account_name, network_name);
-        } else {
-            println!("Transaction history for account '{}' on network '{}':", account_name, network_name);
-            for (index, tx) in history.iter().enumerate() {
-                println!("Transaction {}:", index + 1);
-                println!("  From: {:?}", tx.from_address().unwrap_or(Address::zero()));
-                println!("  To: {:?}", tx.to_address().unwrap_or(Address::zero()));
-                println!("  Value: {:?}", tx.value_as_u256());
-                println!("  Token Value: {:?}", tx.token_value.as_deref().and_then(|v| U256::from_dec_str(v).ok()).unwrap_or(U256::zero()));
-                println!("  Gas Price: {:?}", tx.gas_price_as_u256());
-                println!("  Gas Limit: {:?}", tx.gas_as_u256());
-                println!("--------------------------------");
+            entries.sort_by_key(|(block, _)| *block);
+            let mut new_entries = Vec::new();
+            for (block, mut entry) in entries {
+                if let Some(token) = entry.token_contract_address() {
+                    if let Entry::Vacant(slot) = assets.entry(token) {
+                        slot.insert(self.token_asset_info(token).await);
+                    }
+                    if let Some(asset) = assets.get(&token).filter(|asset| asset.contract.is_some()) {
+                        entry.token_symbol = Some(asset.symbol.clone());
+                        entry.token_decimals = Some(asset.decimals);
+                    }
+                }
+                if let Entry::Vacant(slot) = block_times.entry(block) {
+                    if let Some(found) = provider.get_block(block).await? {
+                        slot.insert(found.timestamp.as_u64());
+                    }
+                }
+                entry.timestamp = block_times.get(&block).copied();
+                new_entries.push(entry);
+            }
+
+            let count = SyncService::merge(&mut history, new_entries);
+            if count > 0 {
+                self.write_history(&history, network_name);
+                added += count;
             }
+            checkpoint.last_block = Some(to);
+            checkpoint.save(&checkpoint_path)?;
         }
+
+        println!("Sync complete: {} new transfers added, synced up to block {}", added, latest);
+        Ok(())
     }
 
-    pub async fn info(&self, tx_hash: &str) -> Result<(), Box<dyn Error>> {
+    pub async fn info(&self, tx_hash: &str, abi_paths: &[String]) -> Result<(), Box<dyn Error>> {
         let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let registry = SelectorRegistry::load(&Self::load_account_name().unwrap_or_default(), abi_paths)?;
 
         let hash: H256 = tx_hash.parse()?;
         let tx = provider.get_transaction(hash).await?;
         if let Some(transaction) = tx {
             println!("Transaction Info:");
             println!("  Hash: {:?}", transaction.hash);
-            println!("  From: {:?}", transaction.from);
-
-            if transaction.input.0.len() >= 68 && transaction.input.0.starts_with(&[0xa9, 0x05, 0x9c, 0xbb]) {
-                let recipient_address = Address::from_slice(&transaction.input.0[16..36]);
-                println!("  To: {:?}", recipient_address);
-                Some(recipient_address)
-            } else {
-                println!("  To: {:?}", transaction.to.unwrap_or_default());
-                transaction.to
-            };
-
-            println!("  Value: {:?}", transaction.value);
+            let address_book = self.address_book();
+            let ens_names = self.ens_names([Some(transaction.from), transaction.to].into_iter().flatten()).await;
+            let describe = |address: &Address| address_book.describe_with_name(address, ens_names.get(address).map(String::as_str));
+            println!("  From: {}", describe(&transaction.from));
+            match transaction.to {
+                Some(to) => println!("  To: {}", describe(&to)),
+                None => println!("  To: (contract creation)"),
+            }
+            println!("  Value: {} {}", format_units(transaction.value, 18)?, self.native_token());
 
-            if let Some(token_value) = transaction.input.0.get(36..68) {
-                let token_amount = U256::from_big_endian(token_value);
-                println!("  Token Value: {}", token_amount);
-            } else {
-                println!("  Token Value: Not applicable");
+            if transaction.to.is_some() && !transaction.input.is_empty() {
+                match registry.decode_call(&transaction.input) {
+                    Some((function, tokens)) => {
+                        println!("  Function: {}", ContractService::input_signature(function));
+                        for argument in SelectorRegistry::describe_arguments(function, &tokens) {
+                            println!("    {}", argument);
+                        }
+                    }
+                    None => println!(
+                        "  Function: unknown selector 0x{}",
+                        hex::encode(transaction.input.get(..4).unwrap_or(&transaction.input))
+                    ),
+                }
             }
 
             println!("  Gas Price: {:?}", transaction.gas_price.unwrap_or_default());
@@ -270,6 +1732,11 @@ impl TransactionService {
             println!("  Block Hash: {:?}", transaction.block_hash.unwrap_or_default());
             println!("  Block Number: {:?}", transaction.block_number.unwrap_or_default());
             println!("  Transaction Index: {:?}", transaction.transaction_index.unwrap_or_default());
+
+            match provider.get_transaction_receipt(hash).await? {
+                Some(receipt) => self.print_receipt(&receipt, transaction.gas, &registry).await?,
+                None => println!("  Status: pending (not yet mined)"),
+            }
         } else {
             println!("Transaction not found for hash: {}", tx_hash);
         }
@@ -277,31 +1744,277 @@ impl TransactionService {
     }
 
 
+    async fn print_receipt(
+        &self,
+        receipt: &TransactionReceipt,
+        gas_limit: U256,
+        registry: &SelectorRegistry,
+    ) -> Result<(), Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let native_token = self.native_token();
+
+        let status = if receipt.status == Some(U64::zero()) { "failed" } else { "success" };
+        println!("  Status: {}", status);
+        if let Some(block_number) = receipt.block_number {
+            let latest = provider.get_block_number().await?;
+            println!("  Confirmations: {}", latest.saturating_sub(block_number) + 1);
+        }
+        if let Some(gas_used) = receipt.gas_used {
+            let percent = if gas_limit.is_zero() { 0 } else { (gas_used * U256::from(100) / gas_limit).as_u64() };
+            println!("  Gas Used: {} ({}% of limit)", gas_used, percent);
+            if let Some(gas_price) = receipt.effective_gas_price {
+                println!("  Effective Gas Price: {} gwei", format_units(gas_price, 9)?);
+                println!("  Fee Paid: {} {}", format_units(gas_used * gas_price, 18)?, native_token);
+            }
+        }
+        if let Some(contract_address) = receipt.contract_address {
+            println!("  Contract Created: {}", checksum(&contract_address));
+        }
+
+        println!("  Logs: {}", receipt.logs.len());
+        let mut assets: HashMap<Address, AssetInfo> = HashMap::new();
+        for (index, log) in receipt.logs.iter().enumerate() {
+            let Some((event, decoded)) = registry.decode_log(log) else {
+                let topic = log.topics.first().map(|topic| format!("{:#x}", topic)).unwrap_or("none".to_string());
+                println!("    [{}] {}: unknown event (topic {})", index, checksum(&log.address), topic);
+                continue;
+            };
+            println!("    [{}] {}: {}", index, checksum(&log.address), event.name);
+
+            // ERC-20 amounts are also shown in token units.
+            let token_amount = matches!(event.name.as_str(), "Transfer" | "Approval") && log.topics.len() == 3;
+            for (param, input) in decoded.params.iter().zip(&event.inputs) {
+                let mut value = ContractService::format_token(&param.value);
+                if let (true, Token::Uint(amount)) = (token_amount, &param.value) {
+                    if let Entry::Vacant(slot) = assets.entry(log.address) {
+                        slot.insert(self.token_asset_info(log.address).await);
+                    }
+                    if let Some(asset) = assets.get(&log.address).filter(|asset| asset.contract.is_some()) {
+                        value = format!("{} ({} {})", value, format_units(*amount, asset.decimals)?, asset.symbol);
+                    }
+                }
+                println!("        {} ({}): {}", param.name, input.kind, value);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn read_contract(
+        &self,
+        contract: &str,
+        signature: &str,
+        args: &[String],
+        abi_path: Option<&str>,
+        block: Option<&str>,
+        as_json: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let contract_address = parse_address(contract, "contract")?;
+        let function = ContractService::resolve_function(signature, abi_path)?;
+        let data = ContractService::encode_call(&function, args)?;
+        let block = match block {
+            Some(block) => Some(BlockId::Number(BlockNumber::from_str(block).map_err(|_| "Invalid block number format")?)),
+            None => None,
+        };
+
+        let mut tx = TransactionRequest::new().to(contract_address).data(data);
+        if let Some(wallet) = self.wallet.as_ref() {
+            tx = tx.from(wallet.address());
+        }
+        let output = provider.call(&tx.into(), block).await?;
+
+        if function.outputs.is_empty() {
+            if as_json {
+                println!("{}", json!({ "raw": format!("{}", output) }));
+            } else {
+                println!("No output types given; raw result: {}", output);
+            }
+            return Ok(());
+        }
+
+        let tokens = function.decode_output(&output).map_err(|e| format!("Failed to decode result: {}", e))?;
+        if as_json {
+            let result: serde_json::Map<String, serde_json::Value> = tokens
+                .iter()
+                .enumerate()
+                .map(|(index, token)| (ContractService::output_name(&function, index), ContractService::token_to_json(token)))
+                .collect();
+            println!("{}", serde_json::Value::Object(result));
+        } else {
+            println!("Result of {}:", ContractService::describe_call(&function, args));
+            for (index, token) in tokens.iter().enumerate() {
+                println!(
+                    "  {} ({}): {}",
+                    ContractService::output_name(&function, index),
+                    function.outputs[index].kind,
+                    ContractService::format_token(token)
+                );
+            }
+        }
+        Ok(())
+    }
+
     pub async fn get_balance(&self, native_token: String) -> Result<(), Box<dyn Error>> {
         let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
         let provider = self.provider.as_ref().ok_or("Provider not set")?;
-        println!("Wallet address: {:?}", wallet.address());
+        println!("Wallet address: {}", checksum(&wallet.address()));
         let balance = provider.get_balance(wallet.address(), None).await?;
         let balance_eth = Self::wei_to_eth(balance);
         println!("Account balance: {} {}", balance_eth, native_token);
         Ok(())
     }
 
+    /// Balance of a token given by address or by the symbol of a tracked token; tracked tokens are shown in human
+    /// units.
     pub async fn get_token_balance(
         &self,
-        token_address: &str,
+        token: &str,
+        network_name: &str,
     ) -> Result<U256, Box<dyn Error>> {
-        let token_address = Address::from_str(token_address).map_err(|_| "Invalid token address format")?;
+        let tokens = TokenList::load(&self.token_list_file(network_name))?;
+        let tracked = tokens.find(token).cloned();
+        let token_address = match &tracked {
+            Some(tracked) => tracked.contract_address().ok_or("Invalid tracked token address")?,
+            None => parse_address(token, "token")?,
+        };
         let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let balance = self.token_balance_of(token_address, wallet.address()).await?;
+        match tracked {
+            Some(tracked) => println!(
+                "Account balance: {} {} ({})",
+                format_units(balance, tracked.decimals)?,
+                tracked.symbol,
+                checksum(&token_address)
+            ),
+            None => println!("Account balance: {} {}", balance, checksum(&token_address)),
+        }
+        Ok(balance)
+    }
+
+    async fn token_balance_of(&self, token_address: Address, owner: Address) -> Result<U256, Box<dyn Error>> {
         let provider = self.provider.as_ref().ok_or("Provider not set")?;
         let abi: Abi = serde_json::from_str(ERC20_ABI)?;
         let contract = Contract::new(token_address, abi, provider.clone());
-        let balance: U256 = contract
-            .method::<_, U256>("balanceOf", wallet.address())?
-            .call()
-            .await?;
-        println!("Account balance: {} {:?}", balance, token_address);
-        Ok(balance)
+        Ok(contract.method::<_, U256>("balanceOf", owner)?.call().await?)
+    }
+
+    /// Native balance and the balance of every tracked token on the network, in human units.
+    pub async fn portfolio(&self, network_name: &str) -> Result<(), Box<dyn Error>> {
+        let wallet = self.wallet.as_ref().ok_or("Wallet not set")?;
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let tokens = TokenList::load(&self.token_list_file(network_name))?;
+
+        println!("Portfolio of {} on '{}':", checksum(&wallet.address()), network_name);
+        let balance = provider.get_balance(wallet.address(), None).await?;
+        println!("  {:<10} {:>30}  native", self.native_token(), format_units(balance, 18)?);
+
+        for token in &tokens.tokens {
+            let Some(token_address) = token.contract_address() else {
+                println!("  {:<10} {:>30}  invalid address {}", token.symbol, "-", token.address);
+                continue;
+            };
+            match self.token_balance_of(token_address, wallet.address()).await {
+                Ok(balance) => println!(
+                    "  {:<10} {:>30}  {}",
+                    token.symbol,
+                    format_units(balance, token.decimals)?,
+                    checksum(&token_address)
+                ),
+                Err(e) => println!("  {:<10} {:>30}  {} (balance unavailable: {})", token.symbol, "-", checksum(&token_address), e),
+            }
+        }
+        if tokens.tokens.is_empty() {
+            println!("No tracked tokens on this network. Add one with `token add` or `token import`.");
+        }
+        Ok(())
+    }
+
+    /// Tracks an ERC-20 token on the network, reading its symbol and decimals from the contract.
+    pub async fn add_token(&self, token_address: &str, network_name: &str) -> Result<(), Box<dyn Error>> {
+        let token_address = parse_address(token_address, "token")?;
+        let path = self.token_list_file(network_name);
+        let mut tokens = TokenList::load(&path)?;
+        if tokens.contains(token_address) {
+            println!("Token {} is already tracked on '{}'.", checksum(&token_address), network_name);
+            return Ok(());
+        }
+
+        let (symbol, decimals) = self
+            .token_metadata(token_address)
+            .await
+            .ok_or(format!("{} does not answer ERC-20 symbol and supported decimals", checksum(&token_address)))?;
+        if tokens.find(&symbol).is_some() {
+            println!("Warning: another tracked token already uses the symbol {}; select it by address.", symbol);
+        }
+        tokens.add(TrackedToken {
+            address: checksum(&token_address),
+            symbol: symbol.clone(),
+            decimals,
+            name: None,
+        });
+        tokens.save(&path)?;
+        println!("Tracking {} ({}, {} decimals) on '{}'.", symbol, checksum(&token_address), decimals, network_name);
+        Ok(())
+    }
+
+    pub fn remove_token(&self, token: &str, network_name: &str) -> Result<(), Box<dyn Error>> {
+        let path = self.token_list_file(network_name);
+        let mut tokens = TokenList::load(&path)?;
+        let removed = tokens
+            .remove(token)
+            .ok_or(format!("Token '{}' is not tracked on '{}'", token, network_name))?;
+        tokens.save(&path)?;
+        println!("Stopped tracking {} ({}) on '{}'.", removed.symbol, checksum_str(&removed.address), network_name);
+        Ok(())
+    }
+
+    pub fn list_tokens(&self, network_name: &str) -> Result<(), Box<dyn Error>> {
+        let tokens = TokenList::load(&self.token_list_file(network_name))?;
+        if tokens.tokens.is_empty() {
+            println!("No tracked tokens on '{}'.", network_name);
+            return Ok(());
+        }
+        println!("Tracked tokens on '{}':", network_name);
+        for token in &tokens.tokens {
+            let name = token.name.as_deref().map(|name| format!("  {}", name)).unwrap_or_default();
+            println!(
+                "  {:<10} {}  {} decimals{}",
+                token.symbol,
+                checksum_str(&token.address),
+                token.decimals,
+                name
+            );
+        }
+        Ok(())
+    }
+
+    /// Imports the entries of a Uniswap-format token list that belong to the network's chain.
+    pub fn import_token_list(&self, file: &str, network_name: &str) -> Result<(), Box<dyn Error>> {
+        let network = self.network.as_ref().ok_or("Network not set")?;
+        let list: UniswapTokenList = serde_json::from_str(&fs::read_to_string(file)?)
+            .map_err(|e| format!("Invalid token list {}: {}", file, e))?;
+
+        let path = self.token_list_file(network_name);
+        let mut tokens = TokenList::load(&path)?;
+        let summary = tokens.import(&list, network.chain_id);
+        tokens.save(&path)?;
+
+        println!(
+            "Imported {} token(s) from {} into '{}'.",
+            summary.added,
+            list.name.as_deref().unwrap_or(file),
+            network_name
+        );
+        if summary.already_tracked > 0 {
+            println!("  {} already tracked", summary.already_tracked);
+        }
+        if summary.other_chains > 0 {
+            println!("  {} skipped for other chains (this network is chain {})", summary.other_chains, network.chain_id);
+        }
+        if summary.invalid > 0 {
+            println!("  {} skipped with an invalid address", summary.invalid);
+        }
+        Ok(())
     }
 
     fn wei_to_eth(wei: U256) -> String {
@@ -334,45 +2047,128 @@ impl TransactionService {
             .join("tx_history.json")
     }
 
-    fn load_history_from_file(&self, network_name: &str) -> Vec<StoredTransaction> {
+    fn token_list_file(&self, network_name: &str) -> PathBuf {
+        TokenList::path(&Self::load_account_name().unwrap_or_default(), network_name)
+    }
+
+    fn load_history_from_file(&self, network_name: &str) -> Result<Vec<StoredTransaction>, Box<dyn Error>> {
+        self.load_network_history(network_name, self.network.as_ref().map(|network| network.chain_id))
+    }
+
+    /// Fails when the file cannot be read or is newer than this version; callers must not write history then, or
+    /// the file would be overwritten.
+    fn load_network_history(
+        &self,
+        network_name: &str,
+        chain_id: Option<u64>,
+    ) -> Result<Vec<StoredTransaction>, Box<dyn Error>> {
         let path = self.tx_history_file(network_name);
 
-        if !path.exists() {
-            println!("Transaction history file does not exist at {:?}", path);
-            return Vec::new();
+        let (history, upgraded) = HistoryService::load(&path, chain_id)
+            .map_err(|e| format!("Failed to read transaction history at {:?}: {}", path, e))?;
+        if upgraded {
+            match HistoryService::save(&path, &history) {
+                Ok(()) => println!("Upgraded transaction history at {:?} to the current format", path),
+                Err(e) => println!("Failed to upgrade transaction history: {}", e),
+            }
         }
+        Ok(history)
+    }
 
-        let file = OpenOptions::new()
-            .read(true)
-            .open(&path)
-            .expect("Failed to open transaction history file");
-        let reader = BufReader::new(file);
+    fn write_history(&self, history: &[StoredTransaction], network_name: &str) {
+        if let Err(e) = HistoryService::save(&self.tx_history_file(network_name), history) {
+            println!("Failed to write transaction history: {}", e);
+        }
+    }
 
-        serde_json::from_reader(reader).unwrap_or_else(|e| {
-            println!("Deserialization error: {}", e);
-            Vec::new()
-        })
+    async fn save_history_to_file(&self, tx: &TypedTransaction, tx_hash: H256, network_name: &str) {
+        let mut stored = StoredTransaction::from_typed_transaction(tx, tx_hash);
+        if let Some(token) = stored.token_contract_address() {
+            let asset = self.token_asset_info(token).await;
+            if asset.contract.is_some() {
+                stored.token_symbol = Some(asset.symbol);
+                stored.token_decimals = Some(asset.decimals);
+            }
+        }
+        self.append_history(stored, network_name);
     }
 
-    fn save_history_to_file(&self, tx: &TypedTransaction, network_name: &str) {
-        let path = self.tx_history_file(network_name);
+    fn append_history(&self, stored: StoredTransaction, network_name: &str) {
+        let mut history = match self.load_history_from_file(network_name) {
+            Ok(history) => history,
+            Err(e) => {
+                println!("Transaction history not updated: {}", e);
+                return;
+            }
+        };
+        history.push(stored);
+        self.write_history(&history, network_name);
+    }
 
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).expect("Failed to create account directory");
+    fn record_receipt(&self, hash: &H256, receipt: &TransactionReceipt, network_name: &str) {
+        let mut history = match self.load_history_from_file(network_name) {
+            Ok(history) => history,
+            Err(e) => {
+                println!("Transaction history not updated: {}", e);
+                return;
+            }
+        };
+        if let Some(tx) = history.iter_mut().find(|tx| tx.hash_matches(hash)) {
+            tx.apply_receipt(receipt);
+            self.write_history(&history, network_name);
         }
+    }
 
-        let mut history = self.load_history_from_file(network_name);
-
-        history.push(StoredTransaction::from_typed_transaction(tx));
+    fn mark_replaced(&self, hash: &H256, network_name: &str) {
+        let mut history = match self.load_history_from_file(network_name) {
+            Ok(history) => history,
+            Err(e) => {
+                println!("Transaction history not updated: {}", e);
+                return;
+            }
+        };
+        if let Some(tx) = history.iter_mut().find(|tx| tx.hash_matches(hash)) {
+            tx.status = TxStatus::Replaced;
+            self.write_history(&history, network_name);
+        }
+    }
 
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&path)
-            .expect("Failed to open transaction history file");
-        let writer = BufWriter::new(file);
+    /// Resolves pending entries from their receipts and records the fee paid for mined transactions sent from
+    /// the account. A pending entry whose nonce is already used and that the node no longer knows about was replaced
+    /// outside vaulty and is marked as dropped.
+    async fn refresh_statuses(&self, history: &mut [StoredTransaction]) -> Result<bool, Box<dyn Error>> {
+        let provider = self.provider.as_ref().ok_or("Provider not set")?;
+        let account = self.wallet.as_ref().map(|wallet| wallet.address());
+        let mut changed = false;
+        let mut confirmed_nonces: HashMap<Address, U256> = HashMap::new();
 
-        serde_json::to_writer(writer, &history).expect("Failed to write transaction history to file");
+        for tx in history.iter_mut() {
+            let missing_fee = matches!(tx.status, TxStatus::Success | TxStatus::Failed)
+                && tx.fee_paid.is_none()
+                && account.is_some()
+                && tx.sender_address() == account;
+            if tx.status != TxStatus::Pending && !missing_fee {
+                continue;
+            }
+            let Some(hash) = tx.hash_as_h256() else { continue };
+            if let Some(receipt) = provider.get_transaction_receipt(hash).await? {
+                tx.apply_receipt(&receipt);
+                changed = true;
+                continue;
+            }
+            if tx.status != TxStatus::Pending || provider.get_transaction(hash).await?.is_some() {
+                continue;
+            }
+            let (Some(sender), Some(nonce)) = (tx.sender_address(), tx.nonce_as_u256()) else { continue };
+            let confirmed = match confirmed_nonces.entry(sender) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => *entry.insert(provider.get_transaction_count(sender, None).await?),
+            };
+            if nonce < confirmed {
+                tx.status = TxStatus::Dropped;
+                changed = true;
+            }
+        }
+        Ok(changed)
     }
 }
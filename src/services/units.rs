@@ -0,0 +1,79 @@
+use ethers::core::types::U256;
+
+/// Largest number of decimals whose scale (10^decimals) fits in a U256.
+pub const MAX_DECIMALS: u32 = 77;
+
+pub fn check_decimals(decimals: u32) -> Result<(), String> {
+    if decimals > MAX_DECIMALS {
+        return Err(format!("Unsupported number of decimals: {} (at most {})", decimals, MAX_DECIMALS));
+    }
+    Ok(())
+}
+
+pub fn format_units(value: U256, decimals: u32) -> Result<String, String> {
+    check_decimals(decimals)?;
+    let base = U256::exp10(decimals as usize);
+    let whole = value / base;
+    let fraction = value % base;
+    if fraction.is_zero() {
+        return Ok(whole.to_string());
+    }
+
+    let fraction = format!("{:0>width$}", fraction.to_string(), width = decimals as usize);
+    Ok(format!("{}.{}", whole, fraction.trim_end_matches('0')))
+}
+
+pub fn parse_units(value: &str, decimals: u32) -> Result<U256, String> {
+    check_decimals(decimals)?;
+    let invalid = || format!("Invalid amount '{}'", value);
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+    if fraction.len() > decimals as usize {
+        return Err(format!("Amount '{}' has more than {} decimal places", value, decimals));
+    }
+    let whole = if whole.is_empty() { U256::zero() } else { U256::from_dec_str(whole).map_err(|_| invalid())? };
+    let fraction_digits = format!("{:0<width$}", fraction, width = decimals as usize);
+    let fraction = if fraction_digits.is_empty() {
+        U256::zero()
+    } else {
+        U256::from_dec_str(&fraction_digits).map_err(|_| invalid())?
+    };
+    whole
+        .checked_mul(U256::exp10(decimals as usize))
+        .and_then(|whole| whole.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_units(U256::exp10(18), 18).unwrap(), "1");
+        assert_eq!(format_units(U256::from(50_000_000_000_000_000u64), 18).unwrap(), "0.05");
+        assert_eq!(format_units(U256::from(1_234_500u64), 6).unwrap(), "1.2345");
+        assert_eq!(format_units(U256::from(42u64), 0).unwrap(), "42");
+    }
+
+    #[test]
+    fn test_out_of_range_decimals() {
+        assert_eq!(format_units(U256::MAX, 77).unwrap().len(), 79);
+        assert!(format_units(U256::one(), 78).is_err());
+        assert!(format_units(U256::one(), u32::MAX).is_err());
+        assert!(parse_units("1", 78).is_err());
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_units("1", 18).unwrap(), U256::exp10(18));
+        assert_eq!(parse_units("0.05", 18).unwrap(), U256::from(50_000_000_000_000_000u64));
+        assert_eq!(parse_units("1.2345", 6).unwrap(), U256::from(1_234_500u64));
+        assert_eq!(parse_units(".5", 1).unwrap(), U256::from(5u64));
+        assert!(parse_units("1.5", 0).is_err());
+        assert!(parse_units("abc", 18).is_err());
+        assert!(parse_units(".", 18).is_err());
+    }
+}